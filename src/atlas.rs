@@ -0,0 +1,756 @@
+//! packs a folder of loose sprite strips into atlas pages and the matching `texture.json`
+//!
+//! every `name.png` in the folder is one animation strip with its frames laid out along x, the
+//! optional `meta.json` next to them holds the per animation data keyed by `name`
+
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+use crate::{RendererError, TextureDescription};
+
+/// per animation metadata for one strip, everything but the frame count has sensible defaults
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, Copy)]
+pub struct StripMeta {
+    #[serde(default = "StripMeta::default_frames")]
+    pub frames: u32,
+    #[serde(default)]
+    pub origin: u32,
+    #[serde(default)]
+    pub looping: u32,
+    #[serde(default = "StripMeta::default_frames_per_sec")]
+    pub frames_per_sec: u32,
+    /// frames per row for strips that are laid out as a grid, 0 is a single row
+    #[serde(default)]
+    pub columns: u32,
+}
+impl StripMeta {
+    fn grid(&self) -> (u32, u32) {
+        TextureDescription {
+            frames: self.frames,
+            columns: self.columns,
+            ..Default::default()
+        }
+        .grid()
+    }
+
+    fn default_frames() -> u32 {
+        1
+    }
+
+    fn default_frames_per_sec() -> u32 {
+        12
+    }
+}
+impl Default for StripMeta {
+    fn default() -> Self {
+        Self {
+            frames: Self::default_frames(),
+            origin: 0,
+            looping: 0,
+            frames_per_sec: Self::default_frames_per_sec(),
+            columns: 0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct PackOptions {
+    /// upper bound of a single page, pages are trimmed to what they actually use
+    pub page_size: (u32, u32),
+    /// empty pixels kept around every strip, stops neighbours bleeding into each other
+    pub padding: u32,
+    pub max_pages: u32,
+}
+impl Default for PackOptions {
+    fn default() -> Self {
+        Self {
+            page_size: (4096, 4096),
+            padding: 1,
+            max_pages: 1,
+        }
+    }
+}
+
+/// a strip that could not be placed, with its size in pixels
+#[derive(Debug, Clone)]
+pub struct Rejected {
+    pub name: String,
+    pub width: u32,
+    pub height: u32,
+}
+
+#[derive(Debug)]
+pub struct PackedAtlas {
+    pub pages: Vec<image::RgbaImage>,
+    pub descriptions: HashMap<String, TextureDescription>,
+    pub rejected: Vec<Rejected>,
+}
+impl PackedAtlas {
+    /// writes `texture.png`, `texture_1.png` and so on plus `texture.json` into `dir`, returns the
+    /// page paths in the order `RendererConfig::texture_pages` expects them
+    pub fn save(&self, dir: impl AsRef<Path>) -> Result<Vec<PathBuf>, RendererError> {
+        let dir = dir.as_ref();
+        std::fs::create_dir_all(dir).map_err(|err| RendererError::AssetIo {
+            path: dir.to_path_buf(),
+            source: err,
+        })?;
+
+        let mut page_paths = vec![];
+        for (index, page) in self.pages.iter().enumerate() {
+            let path = if index == 0 {
+                dir.join("texture.png")
+            } else {
+                dir.join(format!("texture_{}.png", index))
+            };
+            page.save(&path).map_err(|err| RendererError::ImageSave {
+                path: path.clone(),
+                source: err,
+            })?;
+            page_paths.push(path);
+        }
+
+        let path = dir.join("texture.json");
+        // sorted so that the output is stable between runs
+        let sorted: std::collections::BTreeMap<_, _> = self.descriptions.iter().collect();
+        let json =
+            serde_json::to_vec_pretty(&sorted).map_err(|err| RendererError::AssetDecode {
+                path: path.clone(),
+                source: Box::new(err),
+            })?;
+        std::fs::write(&path, json).map_err(|err| RendererError::AssetIo { path, source: err })?;
+        Ok(page_paths)
+    }
+}
+
+/// bottom left skyline, every node is a horizontal segment of the upper outline of what has been placed
+struct Skyline {
+    width: u32,
+    height: u32,
+    // x, y, width
+    nodes: Vec<(u32, u32, u32)>,
+    used: (u32, u32),
+}
+impl Skyline {
+    fn new(width: u32, height: u32, padding: u32) -> Self {
+        Self {
+            width,
+            height,
+            nodes: vec![(padding, padding, width.saturating_sub(padding))],
+            used: (0, 0),
+        }
+    }
+
+    /// lowest y a rect of this width could rest on when its left edge is on the node at `index`
+    fn fit(&self, index: usize, width: u32, height: u32) -> Option<u32> {
+        let x = self.nodes[index].0;
+        if x + width > self.width {
+            return None;
+        }
+        let mut y = 0;
+        let mut remaining = width as i64;
+        let mut each = index;
+        while remaining > 0 {
+            let node = self.nodes.get(each)?;
+            y = y.max(node.1);
+            if y + height > self.height {
+                return None;
+            }
+            remaining -= node.2 as i64;
+            each += 1;
+        }
+        Some(y)
+    }
+
+    /// the padded size goes in, the top left corner of the placed rect comes out
+    fn insert(&mut self, width: u32, height: u32) -> Option<(u32, u32)> {
+        let mut best = None::<(usize, u32)>;
+        for index in 0..self.nodes.len() {
+            if let Some(y) = self.fit(index, width, height) {
+                if best.map_or(true, |(_, best_y)| y < best_y) {
+                    best = Some((index, y));
+                }
+            }
+        }
+        let (index, y) = best?;
+        let x = self.nodes[index].0;
+
+        self.nodes.insert(index, (x, y + height, width));
+        // cut away whatever the new node now covers
+        let right = x + width;
+        while index + 1 < self.nodes.len() {
+            let next = &mut self.nodes[index + 1];
+            if next.0 >= right {
+                break;
+            }
+            let overlap = right - next.0;
+            if overlap >= next.2 {
+                self.nodes.remove(index + 1);
+            } else {
+                next.0 += overlap;
+                next.2 -= overlap;
+                break;
+            }
+        }
+        // merge neighbours of the same height
+        let mut each = 0;
+        while each + 1 < self.nodes.len() {
+            if self.nodes[each].1 == self.nodes[each + 1].1 {
+                self.nodes[each].2 += self.nodes[each + 1].2;
+                self.nodes.remove(each + 1);
+            } else {
+                each += 1;
+            }
+        }
+
+        self.used.0 = self.used.0.max(right);
+        self.used.1 = self.used.1.max(y + height);
+        Some((x, y))
+    }
+}
+
+/// packs already loaded strips, larger strips are placed first, whatever does not fit into
+/// `max_pages` pages ends up in `rejected`
+pub fn pack(
+    strips: Vec<(String, image::RgbaImage, StripMeta)>,
+    options: PackOptions,
+) -> Result<PackedAtlas, RendererError> {
+    let mut strips = strips;
+    strips
+        .sort_by(|x, y| (y.1.height(), y.1.width(), &x.0).cmp(&(x.1.height(), x.1.width(), &y.0)));
+
+    let padding = options.padding;
+    let mut skylines: Vec<Skyline> = vec![];
+    let mut placements = vec![];
+    let mut rejected = vec![];
+    for (name, strip, meta) in strips {
+        let (columns, rows) = meta.grid();
+        if strip.width() % columns != 0 || strip.height() % rows != 0 {
+            return Err(RendererError::InvalidTextureDescription {
+                name,
+                reason: "strip size is not a multiple of its frame grid",
+            });
+        }
+
+        let padded = (strip.width() + padding, strip.height() + padding);
+        let mut placed = None;
+        for (page, skyline) in skylines.iter_mut().enumerate() {
+            if let Some(pos) = skyline.insert(padded.0, padded.1) {
+                placed = Some((page, pos));
+                break;
+            }
+        }
+        if placed.is_none() && (skylines.len() as u32) < options.max_pages {
+            let mut skyline = Skyline::new(options.page_size.0, options.page_size.1, padding);
+            placed = skyline
+                .insert(padded.0, padded.1)
+                .map(|pos| (skylines.len(), pos));
+            // a strip that doesn't even fit an empty page shouldn't open one
+            if placed.is_some() {
+                skylines.push(skyline);
+            }
+        }
+
+        match placed {
+            Some((page, (x, y))) => placements.push((name, strip, meta, page, x, y)),
+            None => rejected.push(Rejected {
+                name,
+                width: strip.width(),
+                height: strip.height(),
+            }),
+        }
+    }
+
+    let mut pages: Vec<image::RgbaImage> = skylines
+        .iter()
+        .map(|x| image::RgbaImage::new(x.used.0.max(1), x.used.1.max(1)))
+        .collect();
+    let mut descriptions = HashMap::new();
+    for (name, strip, meta, page, x, y) in placements {
+        image::imageops::replace(&mut pages[page], &strip, x as i64, y as i64);
+        let (columns, rows) = meta.grid();
+        descriptions.insert(
+            name,
+            TextureDescription {
+                tex_x: x,
+                tex_y: y,
+                tex_width: strip.width() / columns,
+                tex_height: strip.height() / rows,
+                frames: meta.frames.max(1),
+                origin: meta.origin,
+                looping: meta.looping,
+                frames_per_sec: meta.frames_per_sec,
+                page: page as u32,
+                reversed: 0,
+                columns: meta.columns,
+                ..Default::default()
+            },
+        );
+    }
+
+    Ok(PackedAtlas {
+        pages,
+        descriptions,
+        rejected,
+    })
+}
+
+/// loads every png of `dir` together with `meta.json` if there is one and packs them
+pub fn pack_directory(
+    dir: impl AsRef<Path>,
+    options: PackOptions,
+) -> Result<PackedAtlas, RendererError> {
+    let dir = dir.as_ref();
+    let meta_path = dir.join("meta.json");
+    let mut meta: HashMap<String, StripMeta> = if meta_path.is_file() {
+        let val = std::fs::read(&meta_path).map_err(|err| RendererError::AssetIo {
+            path: meta_path.clone(),
+            source: err,
+        })?;
+        serde_json::from_slice(&val).map_err(|err| RendererError::AssetDecode {
+            path: meta_path.clone(),
+            source: Box::new(err),
+        })?
+    } else {
+        HashMap::new()
+    };
+
+    let entries = std::fs::read_dir(dir).map_err(|err| RendererError::AssetIo {
+        path: dir.to_path_buf(),
+        source: err,
+    })?;
+    let mut strips = vec![];
+    for entry in entries {
+        let path = entry
+            .map_err(|err| RendererError::AssetIo {
+                path: dir.to_path_buf(),
+                source: err,
+            })?
+            .path();
+        if path.extension().and_then(|x| x.to_str()) != Some("png") {
+            continue;
+        }
+        let Some(name) = path.file_stem().and_then(|x| x.to_str()) else {
+            continue;
+        };
+        let name = name.to_string();
+        let strip = image::io::Reader::open(&path)
+            .map_err(|err| RendererError::AssetIo {
+                path: path.clone(),
+                source: err,
+            })?
+            .decode()
+            .map_err(|err| RendererError::AssetDecode {
+                path: path.clone(),
+                source: Box::new(err),
+            })?
+            .into_rgba8();
+        let strip_meta = meta.remove(&name).unwrap_or_default();
+        strips.push((name, strip, strip_meta));
+    }
+
+    pack(strips, options)
+}
+
+#[derive(serde::Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+struct AsepriteRect {
+    x: u32,
+    y: u32,
+    w: u32,
+    h: u32,
+}
+
+#[derive(serde::Deserialize, Debug, Clone, Copy)]
+struct AsepriteFrame {
+    frame: AsepriteRect,
+    /// milliseconds
+    duration: u32,
+}
+
+/// aseprite writes frames either as an array or as an object keyed by file name, in the latter the
+/// order of the keys is the frame order so it has to be kept
+#[derive(Debug)]
+struct AsepriteFrames(Vec<AsepriteFrame>);
+impl<'de> serde::Deserialize<'de> for AsepriteFrames {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct Visitor;
+        impl<'de> serde::de::Visitor<'de> for Visitor {
+            type Value = AsepriteFrames;
+
+            fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                write!(f, "an array or a map of frames")
+            }
+
+            fn visit_seq<A: serde::de::SeqAccess<'de>>(
+                self,
+                mut seq: A,
+            ) -> Result<Self::Value, A::Error> {
+                let mut frames = vec![];
+                while let Some(frame) = seq.next_element()? {
+                    frames.push(frame);
+                }
+                Ok(AsepriteFrames(frames))
+            }
+
+            fn visit_map<A: serde::de::MapAccess<'de>>(
+                self,
+                mut map: A,
+            ) -> Result<Self::Value, A::Error> {
+                let mut frames = vec![];
+                while let Some((_, frame)) = map.next_entry::<String, AsepriteFrame>()? {
+                    frames.push(frame);
+                }
+                Ok(AsepriteFrames(frames))
+            }
+        }
+        deserializer.deserialize_any(Visitor)
+    }
+}
+
+#[derive(serde::Deserialize, Debug)]
+struct AsepriteTag {
+    name: String,
+    from: u32,
+    to: u32,
+    #[serde(default)]
+    direction: String,
+    /// only present when the tag plays a fixed number of times
+    #[serde(default)]
+    repeat: Option<String>,
+}
+
+#[derive(serde::Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct AsepriteMeta {
+    image: String,
+    #[serde(default)]
+    frame_tags: Vec<AsepriteTag>,
+}
+
+#[derive(serde::Deserialize, Debug)]
+struct AsepriteSheet {
+    frames: AsepriteFrames,
+    meta: AsepriteMeta,
+}
+
+/// reads an aseprite sprite sheet export, every tag becomes an entry named `<file stem>_<tag>`, a
+/// sheet without tags becomes a single entry named after the file
+///
+/// the frames of a tag have to sit next to each other, wrapping onto the next row at the x of the
+/// first frame, which is what the horizontal strip and by rows sheet types give you, returns the
+/// path of the sheet image next to the entries which all point at page 0
+pub fn load_aseprite(
+    path: impl AsRef<Path>,
+) -> Result<(PathBuf, HashMap<String, TextureDescription>), RendererError> {
+    let path = path.as_ref();
+    let val = std::fs::read(path).map_err(|err| RendererError::AssetIo {
+        path: path.to_path_buf(),
+        source: err,
+    })?;
+    let sheet: AsepriteSheet =
+        serde_json::from_slice(&val).map_err(|err| RendererError::AssetDecode {
+            path: path.to_path_buf(),
+            source: Box::new(err),
+        })?;
+    let stem = path
+        .file_stem()
+        .and_then(|x| x.to_str())
+        .unwrap_or_default()
+        .to_string();
+    let frames = &sheet.frames.0;
+
+    let mut tags = sheet.meta.frame_tags;
+    if tags.is_empty() && !frames.is_empty() {
+        tags.push(AsepriteTag {
+            name: String::new(),
+            from: 0,
+            to: frames.len() as u32 - 1,
+            direction: String::new(),
+            repeat: None,
+        });
+    }
+
+    let mut descriptions = HashMap::new();
+    for tag in tags {
+        let name = if tag.name.is_empty() {
+            stem.clone()
+        } else {
+            format!("{}_{}", stem, tag.name)
+        };
+        let tag_frames = frames
+            .get(tag.from as usize..=tag.to as usize)
+            .filter(|x| !x.is_empty())
+            .ok_or_else(|| RendererError::InvalidTextureDescription {
+                name: name.clone(),
+                reason: "tag refers to frames that don't exist",
+            })?;
+
+        let first = tag_frames[0].frame;
+        let first_duration = tag_frames[0].duration;
+        // the first row decides how many columns the grid has
+        let columns = tag_frames
+            .iter()
+            .take_while(|x| x.frame.y == first.y)
+            .count() as u32;
+        for (index, each) in tag_frames.iter().enumerate() {
+            let index = index as u32;
+            let expected = AsepriteRect {
+                x: first.x + index % columns * first.w,
+                y: first.y + index / columns * first.h,
+                ..first
+            };
+            if each.frame != expected {
+                return Err(RendererError::InvalidTextureDescription {
+                    name,
+                    reason: "tag frames are not laid out as a grid",
+                });
+            }
+        }
+
+        let total_duration: u32 = tag_frames.iter().map(|x| x.duration.max(1)).sum();
+        let frames_per_sec =
+            (1000.0 * tag_frames.len() as f32 / total_duration as f32).round() as u32;
+        // only held key poses need the per frame list, everything else is covered by the fps
+        let frame_durations = tag_frames
+            .iter()
+            .any(|x| x.duration != first_duration)
+            .then(|| {
+                tag_frames
+                    .iter()
+                    .map(|x| x.duration as f32 / 1000.0)
+                    .collect()
+            });
+        let reversed = matches!(tag.direction.as_str(), "reverse" | "pingpong_reverse") as u32;
+        let ping_pong = matches!(tag.direction.as_str(), "pingpong" | "pingpong_reverse") as u32;
+        let looping = tag.repeat.as_deref().map_or(true, |x| x == "0") as u32;
+
+        descriptions.insert(
+            name,
+            TextureDescription {
+                tex_x: first.x,
+                tex_y: first.y,
+                tex_width: first.w,
+                tex_height: first.h,
+                frames: tag_frames.len() as u32,
+                origin: 0,
+                looping,
+                frames_per_sec: frames_per_sec.max(1),
+                page: 0,
+                reversed,
+                ping_pong,
+                frame_durations,
+                columns: if columns == tag_frames.len() as u32 {
+                    0
+                } else {
+                    columns
+                },
+                ..Default::default()
+            },
+        );
+    }
+
+    let image = path
+        .parent()
+        .unwrap_or_else(|| Path::new(""))
+        .join(sheet.meta.image);
+    Ok((image, descriptions))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strip(name: &str, width: u32, height: u32) -> (String, image::RgbaImage, StripMeta) {
+        (
+            name.to_string(),
+            image::RgbaImage::new(width, height),
+            StripMeta::default(),
+        )
+    }
+
+    /// left, top, right and bottom of every placed strip, per page
+    fn placed_rects(atlas: &PackedAtlas) -> Vec<(u32, [u32; 4])> {
+        atlas
+            .descriptions
+            .values()
+            .map(|x| {
+                let (columns, rows) = x.grid();
+                let right = x.tex_x + x.tex_width * columns;
+                let bottom = x.tex_y + x.tex_height * rows;
+                (x.page, [x.tex_x, x.tex_y, right, bottom])
+            })
+            .collect()
+    }
+
+    fn assert_packed(atlas: &PackedAtlas, options: PackOptions) {
+        let rects = placed_rects(atlas);
+        for (index, (page, rect)) in rects.iter().enumerate() {
+            assert!(rect[2] <= options.page_size.0 && rect[3] <= options.page_size.1);
+            let image = &atlas.pages[*page as usize];
+            assert!(rect[2] <= image.width() && rect[3] <= image.height());
+            for (other_page, other) in &rects[index + 1..] {
+                let apart = rect[2] + options.padding <= other[0]
+                    || other[2] + options.padding <= rect[0]
+                    || rect[3] + options.padding <= other[1]
+                    || other[3] + options.padding <= rect[1];
+                assert!(
+                    page != other_page || apart,
+                    "{:?} overlaps {:?}",
+                    rect,
+                    other
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn packed_strips_stay_apart_and_inside_the_page() {
+        let options = PackOptions {
+            page_size: (128, 128),
+            padding: 1,
+            max_pages: 1,
+        };
+        let strips = (0..20)
+            .map(|x| strip(&format!("strip_{}", x), 8 + x * 3 % 24, 6 + x * 5 % 14))
+            .collect();
+        let atlas = pack(strips, options).unwrap();
+        assert!(atlas.rejected.is_empty());
+        assert_eq!(atlas.descriptions.len(), 20);
+        assert_packed(&atlas, options);
+    }
+
+    #[test]
+    fn oversized_strip_is_rejected_without_opening_a_page() {
+        let options = PackOptions {
+            page_size: (64, 64),
+            padding: 1,
+            max_pages: 4,
+        };
+        let atlas = pack(vec![strip("huge", 100, 10), strip("small", 8, 8)], options).unwrap();
+        assert_eq!(atlas.rejected.len(), 1);
+        assert_eq!(atlas.rejected[0].name, "huge");
+        assert_eq!(atlas.pages.len(), 1);
+        assert!(atlas.descriptions.contains_key("small"));
+    }
+
+    #[test]
+    fn strips_overflow_onto_new_pages_up_to_the_limit() {
+        let options = PackOptions {
+            page_size: (40, 40),
+            padding: 1,
+            max_pages: 2,
+        };
+        // four of these fill a page
+        let strips = (0..10)
+            .map(|x| strip(&format!("strip_{}", x), 18, 18))
+            .collect();
+        let atlas = pack(strips, options).unwrap();
+        assert_eq!(atlas.pages.len(), 2);
+        assert_eq!(atlas.descriptions.len(), 8);
+        assert_eq!(atlas.rejected.len(), 2);
+        assert_packed(&atlas, options);
+    }
+
+    #[test]
+    fn strip_must_match_its_frame_grid() {
+        let mut bad = strip("bad", 10, 8);
+        bad.2.frames = 3;
+        assert!(pack(vec![bad], PackOptions::default()).is_err());
+    }
+
+    /// writes the export into a fresh folder so tests don't trip over each other
+    fn load(
+        name: &str,
+        json: &str,
+    ) -> Result<(PathBuf, HashMap<String, TextureDescription>), RendererError> {
+        let dir = std::env::temp_dir().join(format!("aseprite_{}_{}", name, std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(format!("{}.json", name));
+        std::fs::write(&path, json).unwrap();
+        load_aseprite(path)
+    }
+
+    fn frame(x: u32, y: u32, duration: u32) -> String {
+        format!(
+            r#"{{ "frame": {{ "x": {}, "y": {}, "w": 16, "h": 16 }}, "duration": {} }}"#,
+            x, y, duration
+        )
+    }
+
+    #[test]
+    fn tags_become_entries_with_their_direction() {
+        let frames: Vec<_> = (0..6).map(|x| frame(x * 16, 0, 100)).collect();
+        let json = format!(
+            r#"{{
+                "frames": [{}],
+                "meta": {{
+                    "image": "hero.png",
+                    "frameTags": [
+                        {{ "name": "idle", "from": 0, "to": 1, "direction": "forward" }},
+                        {{ "name": "back", "from": 2, "to": 3, "direction": "reverse", "repeat": "2" }},
+                        {{ "name": "swing", "from": 4, "to": 5, "direction": "pingpong" }}
+                    ]
+                }}
+            }}"#,
+            frames.join(",")
+        );
+        let (image, descriptions) = load("hero", &json).unwrap();
+        assert_eq!(image.file_name().unwrap(), "hero.png");
+        assert_eq!(descriptions.len(), 3);
+
+        let idle = &descriptions["hero_idle"];
+        assert_eq!((idle.tex_x, idle.frames, idle.frames_per_sec), (0, 2, 10));
+        assert_eq!((idle.looping, idle.reversed, idle.ping_pong), (1, 0, 0));
+        assert_eq!(idle.frame_durations, None);
+
+        let back = &descriptions["hero_back"];
+        assert_eq!(back.tex_x, 32);
+        assert_eq!((back.looping, back.reversed, back.ping_pong), (0, 1, 0));
+
+        let swing = &descriptions["hero_swing"];
+        assert_eq!(swing.tex_x, 64);
+        assert_eq!((swing.looping, swing.reversed, swing.ping_pong), (1, 0, 1));
+    }
+
+    #[test]
+    fn keyed_frames_keep_their_order_and_wrap_into_rows() {
+        // frames listed by file name, four per row
+        let frames: Vec<_> = (0..6)
+            .map(|x| {
+                format!(
+                    r#""walk {}.aseprite": {}"#,
+                    x,
+                    frame(x % 4 * 16, x / 4 * 16, 50 + x * 50)
+                )
+            })
+            .collect();
+        let json = format!(
+            r#"{{ "frames": {{ {} }}, "meta": {{ "image": "walk.png" }} }}"#,
+            frames.join(",")
+        );
+        let (_, descriptions) = load("walk", &json).unwrap();
+        let walk = &descriptions["walk"];
+        assert_eq!((walk.frames, walk.columns), (6, 4));
+        assert_eq!(
+            walk.frame_durations,
+            Some(vec![0.05, 0.1, 0.15, 0.2, 0.25, 0.3])
+        );
+    }
+
+    #[test]
+    fn bad_tags_are_rejected() {
+        let frames = vec![frame(0, 0, 100), frame(40, 0, 100)];
+        let scattered = format!(
+            r#"{{ "frames": [{}], "meta": {{ "image": "x.png" }} }}"#,
+            frames.join(",")
+        );
+        assert!(load("scattered", &scattered).is_err());
+
+        let out_of_range = format!(
+            r#"{{ "frames": [{}], "meta": {{ "image": "x.png", "frameTags": [{{ "name": "a", "from": 0, "to": 5 }}] }} }}"#,
+            frame(0, 0, 100)
+        );
+        assert!(load("out_of_range", &out_of_range).is_err());
+    }
+}
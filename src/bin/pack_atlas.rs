@@ -0,0 +1,68 @@
+//! pack_atlas <input_dir> <output_dir> [--page-size WIDTHxHEIGHT] [--padding N] [--max-pages N]
+
+use renderer::atlas::{pack_directory, PackOptions};
+
+fn usage() -> ! {
+    eprintln!(
+        "usage: pack_atlas <input_dir> <output_dir> [--page-size WIDTHxHEIGHT] [--padding N] [--max-pages N]"
+    );
+    std::process::exit(2)
+}
+
+fn parse<T: std::str::FromStr>(value: Option<String>) -> T {
+    value
+        .and_then(|x| x.parse().ok())
+        .unwrap_or_else(|| usage())
+}
+
+fn main() {
+    let mut args = std::env::args().skip(1);
+    let (Some(input_dir), Some(output_dir)) = (args.next(), args.next()) else {
+        usage()
+    };
+
+    let mut options = PackOptions::default();
+    while let Some(flag) = args.next() {
+        match flag.as_str() {
+            "--page-size" => {
+                let size: String = parse(args.next());
+                let Some((width, height)) = size.split_once('x') else {
+                    usage()
+                };
+                options.page_size = (parse(Some(width.into())), parse(Some(height.into())));
+            }
+            "--padding" => options.padding = parse(args.next()),
+            "--max-pages" => options.max_pages = parse(args.next()),
+            _ => usage(),
+        }
+    }
+
+    let atlas = match pack_directory(&input_dir, options) {
+        Ok(atlas) => atlas,
+        Err(err) => {
+            eprintln!("{}", err);
+            std::process::exit(1)
+        }
+    };
+    match atlas.save(&output_dir) {
+        Ok(pages) => {
+            for page in pages {
+                println!("wrote {}", page.display());
+            }
+        }
+        Err(err) => {
+            eprintln!("{}", err);
+            std::process::exit(1)
+        }
+    }
+
+    if !atlas.rejected.is_empty() {
+        for each in &atlas.rejected {
+            eprintln!(
+                "did not fit: {} ({}x{})",
+                each.name, each.width, each.height
+            );
+        }
+        std::process::exit(1)
+    }
+}
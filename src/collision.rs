@@ -0,0 +1,1078 @@
+//! shapes a collision rect can take besides its plain box, the filters deciding which rects meet and
+//! the narrow phase that tests them
+//!
+//! every shape sits centered on the box of its rect, offsets and sizes are in the same units as
+//! the rect with y pointing up, rotations are in radians counter clockwise
+//!
+//! each shape boils down to a convex core, a point, a segment or a polygon, grown by a radius,
+//! two shapes touch when their cores are no further apart than the sum of their radii
+
+use crate::RendererError;
+
+/// most points a convex polygon can have, they are stored inline so shapes stay `Copy`
+pub const MAX_POLYGON_POINTS: usize = 8;
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum CollisionShape {
+    /// fills the box of the rect
+    #[default]
+    Rect,
+    Circle {
+        radius: f32,
+    },
+    /// a segment of `2 * half_length` rounded by `radius`, horizontal before rotating
+    Capsule {
+        half_length: f32,
+        radius: f32,
+        rotation: f32,
+    },
+    OrientedBox {
+        half_width: f32,
+        half_height: f32,
+        rotation: f32,
+    },
+    Polygon(ConvexPolygon),
+}
+
+/// which rects a rect collides with, by bits, a rect sits on the layers of `layer` and wants to
+/// hit the layers of `mask`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CollisionFilter {
+    pub layer: u32,
+    pub mask: u32,
+}
+impl CollisionFilter {
+    /// on every layer and hitting every layer
+    pub const ALL: Self = Self {
+        layer: !0,
+        mask: !0,
+    };
+
+    pub fn new(layer: u32, mask: u32) -> Self {
+        Self { layer, mask }
+    }
+
+    fn wants(&self, other: &Self) -> bool {
+        self.mask & other.layer != 0
+    }
+
+    pub(crate) fn accepts(&self, other: &Self, mode: FilterMode) -> bool {
+        match mode {
+            FilterMode::Symmetric => self.wants(other) && other.wants(self),
+            FilterMode::Asymmetric => self.wants(other) || other.wants(self),
+        }
+    }
+}
+impl Default for CollisionFilter {
+    fn default() -> Self {
+        Self::ALL
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FilterMode {
+    /// both rects of a pair have to want the other
+    #[default]
+    Symmetric,
+    /// one of them wanting the other is enough, say bullets masking enemies while enemies
+    /// don't mask bullets
+    Asymmetric,
+}
+
+/// points relative to the center of the rect, in either winding order
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ConvexPolygon {
+    points: [[f32; 2]; MAX_POLYGON_POINTS],
+    len: u32,
+}
+impl ConvexPolygon {
+    pub fn new(points: &[(f32, f32)]) -> Result<Self, RendererError> {
+        if points.len() < 3 || points.len() > MAX_POLYGON_POINTS {
+            return Err(RendererError::InvalidShape("a polygon needs 3 to 8 points"));
+        }
+        let mut polygon = Self {
+            points: [[0.0; 2]; MAX_POLYGON_POINTS],
+            len: points.len() as u32,
+        };
+        for (each, point) in points.iter().enumerate() {
+            polygon.points[each] = [point.0, point.1];
+        }
+        // every turn has to go the same way
+        let mut winding = 0.0f32;
+        for each in 0..points.len() {
+            let a = polygon.points[each];
+            let b = polygon.points[(each + 1) % points.len()];
+            let c = polygon.points[(each + 2) % points.len()];
+            let turn = cross(sub(b, a), sub(c, b));
+            if turn * winding < 0.0 {
+                return Err(RendererError::InvalidShape("polygon isn't convex"));
+            }
+            if turn != 0.0 {
+                winding = turn;
+            }
+        }
+        if winding == 0.0 {
+            return Err(RendererError::InvalidShape("polygon has no area"));
+        }
+        Ok(polygon)
+    }
+
+    pub fn points(&self) -> impl Iterator<Item = (f32, f32)> + '_ {
+        self.points[..self.len as usize]
+            .iter()
+            .map(|x| (x[0], x[1]))
+    }
+}
+
+/// how two shapes overlap
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Contact {
+    /// unit length, points from the first shape towards the second
+    pub normal: (f32, f32),
+    /// how far the shapes have to move apart along `normal` until they only touch
+    pub depth: f32,
+    /// middle of the overlap, in world space
+    pub point: (f32, f32),
+}
+impl Contact {
+    /// the same contact seen from the second shape
+    pub fn flipped(self) -> Self {
+        Self {
+            normal: (-self.normal.0, -self.normal.1),
+            ..self
+        }
+    }
+}
+
+/// a colliding pair of rects by sparse index, `contact` is seen from `first`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Manifold {
+    pub first: usize,
+    pub second: usize,
+    pub contact: Contact,
+}
+impl Manifold {
+    pub fn flipped(self) -> Self {
+        Self {
+            first: self.second,
+            second: self.first,
+            contact: self.contact.flipped(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CollisionPhase {
+    /// the pair started touching this tick
+    Enter,
+    /// the pair touched on the last tick and still does
+    Stay,
+    /// the pair stopped touching, or one of the rects was removed
+    Exit,
+}
+
+/// a pair of rects by sparse index, `contact` is seen from `first` and is `None` on exit
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CollisionEvent {
+    pub phase: CollisionPhase,
+    pub first: usize,
+    pub second: usize,
+    pub contact: Option<Contact>,
+}
+impl CollisionEvent {
+    pub fn flipped(self) -> Self {
+        Self {
+            first: self.second,
+            second: self.first,
+            contact: self.contact.map(Contact::flipped),
+            ..self
+        }
+    }
+}
+
+/// which rects a query sees, the default sees all of them
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct QueryFilter {
+    /// rects on none of these layers are skipped
+    pub mask: u32,
+    /// depth and fuzzy range as a rect has them, `None` sees every depth
+    pub depth: Option<(f32, f32)>,
+    /// sparse index of the rect doing the query, it and the rects it ignores are skipped
+    pub exclude: Option<usize>,
+}
+impl Default for QueryFilter {
+    fn default() -> Self {
+        Self {
+            mask: !0,
+            depth: None,
+            exclude: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RayHit {
+    pub sparse_index: usize,
+    /// along the ray from its origin, 0 when the origin is inside the shape
+    pub distance: f32,
+    pub point: (f32, f32),
+    /// points out of the shape that was hit, against the ray when the origin is inside it
+    pub normal: (f32, f32),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ShapeHit {
+    pub sparse_index: usize,
+    /// how far the shape moves before it touches, 0 when it starts out overlapping
+    pub distance: f32,
+    /// seen from the moving shape, at the spot where it stopped
+    pub contact: Contact,
+}
+
+/// a shape placed in the world, `points` is its core
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Placed {
+    points: [[f32; 2]; MAX_POLYGON_POINTS],
+    len: usize,
+    radius: f32,
+    // box around the shape, worked out once since the broad phase reads it over and over
+    min: [f32; 2],
+    max: [f32; 2],
+}
+impl Placed {
+    /// `center` and `half_size` describe the box of the rect
+    pub(crate) fn new(shape: &CollisionShape, center: [f32; 2], half_size: [f32; 2]) -> Self {
+        let mut placed = Self {
+            points: [[0.0; 2]; MAX_POLYGON_POINTS],
+            len: 0,
+            radius: 0.0,
+            min: [0.0; 2],
+            max: [0.0; 2],
+        };
+        let mut push = |placed: &mut Self, offset: [f32; 2], rotation: f32| {
+            placed.points[placed.len] = add(center, rotate(offset, rotation));
+            placed.len += 1;
+        };
+        match *shape {
+            CollisionShape::Rect => {
+                let [x, y] = half_size;
+                for offset in [[-x, -y], [x, -y], [x, y], [-x, y]] {
+                    push(&mut placed, offset, 0.0);
+                }
+            }
+            CollisionShape::Circle { radius } => {
+                push(&mut placed, [0.0, 0.0], 0.0);
+                placed.radius = radius;
+            }
+            CollisionShape::Capsule {
+                half_length,
+                radius,
+                rotation,
+            } => {
+                push(&mut placed, [-half_length, 0.0], rotation);
+                push(&mut placed, [half_length, 0.0], rotation);
+                placed.radius = radius;
+            }
+            CollisionShape::OrientedBox {
+                half_width: x,
+                half_height: y,
+                rotation,
+            } => {
+                for offset in [[-x, -y], [x, -y], [x, y], [-x, y]] {
+                    push(&mut placed, offset, rotation);
+                }
+            }
+            CollisionShape::Polygon(polygon) => {
+                for point in &polygon.points[..polygon.len as usize] {
+                    push(&mut placed, *point, 0.0);
+                }
+            }
+        }
+        placed.fit_box();
+        placed
+    }
+
+    fn fit_box(&mut self) {
+        self.min = [f32::INFINITY; 2];
+        self.max = [f32::NEG_INFINITY; 2];
+        for point in &self.points[..self.len] {
+            for axis in 0..2 {
+                self.min[axis] = self.min[axis].min(point[axis] - self.radius);
+                self.max[axis] = self.max[axis].max(point[axis] + self.radius);
+            }
+        }
+    }
+
+    fn core(&self) -> &[[f32; 2]] {
+        &self.points[..self.len]
+    }
+
+    /// min and max corner of the box around the shape, the broad phase sorts these
+    pub(crate) fn aabb(&self) -> ([f32; 2], [f32; 2]) {
+        (self.min, self.max)
+    }
+
+    pub(crate) fn overlaps(&self, other: &Self) -> bool {
+        self.gap(other) <= 0.0
+    }
+
+    pub(crate) fn contains_point(&self, point: [f32; 2]) -> bool {
+        closest_points(self.core(), &[point]).2 <= self.radius
+    }
+
+    /// distance between the shapes, negative or 0 when they overlap
+    fn gap(&self, other: &Self) -> f32 {
+        closest_points(self.core(), other.core()).2 - self.radius - other.radius
+    }
+
+    fn translated(&self, by: [f32; 2]) -> Self {
+        let mut moved = *self;
+        for point in &mut moved.points[..moved.len] {
+            *point = add(*point, by);
+        }
+        moved.min = add(moved.min, by);
+        moved.max = add(moved.max, by);
+        moved
+    }
+
+    /// `direction` has unit length, returns how far along it the shape was hit and the normal there
+    pub(crate) fn raycast(
+        &self,
+        origin: [f32; 2],
+        direction: [f32; 2],
+        max_distance: f32,
+    ) -> Option<(f32, [f32; 2])> {
+        let hit = ray_rounded(origin, direction, self.core(), self.radius)?;
+        if hit.0 > max_distance {
+            return None;
+        }
+        Some(hit)
+    }
+
+    /// sweeps the shape along `direction`, which has unit length, and returns how far it gets
+    /// before touching `target`
+    ///
+    /// moving by `t` touches once `t * direction` lands in the target core minus the moving one,
+    /// grown by both radii, so the exact distance is a raycast from 0 against that shape
+    pub(crate) fn cast(
+        &self,
+        direction: [f32; 2],
+        max_distance: f32,
+        target: &Self,
+    ) -> Option<(f32, Contact)> {
+        if let Some(contact) = self.contact(target) {
+            return Some((0.0, contact));
+        }
+        let mut difference = Vec::with_capacity(self.len * target.len);
+        for target_point in target.core() {
+            for point in self.core() {
+                difference.push(sub(*target_point, *point));
+            }
+        }
+        let difference = convex_hull(difference);
+        let (distance, outward) = ray_rounded(
+            [0.0, 0.0],
+            direction,
+            &difference,
+            self.radius + target.radius,
+        )?;
+        if distance > max_distance {
+            return None;
+        }
+        // the difference's normal points from the target back to the moving shape
+        let normal = scale(outward, -1.0);
+        let moved = self.translated(scale(direction, distance));
+        let (_, on_target, _) = closest_points(moved.core(), target.core());
+        let point = sub(on_target, scale(normal, target.radius));
+        Some((
+            distance,
+            Contact {
+                normal: (normal[0], normal[1]),
+                depth: 0.0,
+                point: (point[0], point[1]),
+            },
+        ))
+    }
+
+    /// `None` when the shapes don't touch
+    pub(crate) fn contact(&self, other: &Self) -> Option<Contact> {
+        let radii = self.radius + other.radius;
+        let (on_self, on_other, distance) = closest_points(self.core(), other.core());
+        if distance > radii {
+            return None;
+        }
+        if distance > f32::EPSILON {
+            // the cores are apart, only the rounded parts overlap
+            let normal = scale(sub(on_other, on_self), 1.0 / distance);
+            let self_surface = add(on_self, scale(normal, self.radius));
+            let other_surface = sub(on_other, scale(normal, other.radius));
+            let point = scale(add(self_surface, other_surface), 0.5);
+            return Some(Contact {
+                normal: (normal[0], normal[1]),
+                depth: radii - distance,
+                point: (point[0], point[1]),
+            });
+        }
+        // the cores overlap, push apart along the axis that needs the least of it, two points on
+        // top of each other have no axis so they go up
+        let (normal, core_depth) =
+            least_overlap(self.core(), other.core()).unwrap_or(([0.0, 1.0], 0.0));
+        let depth = core_depth + radii;
+        // deepest part of the other core, moved to the middle of the overlap
+        let deepest = support(other.core(), scale(normal, -1.0));
+        let point = add(deepest, scale(normal, depth / 2.0 - other.radius));
+        Some(Contact {
+            normal: (normal[0], normal[1]),
+            depth,
+            point: (point[0], point[1]),
+        })
+    }
+}
+
+/// the closest point on each core and their distance, 0 when the cores overlap
+fn closest_points(a: &[[f32; 2]], b: &[[f32; 2]]) -> ([f32; 2], [f32; 2], f32) {
+    if contains(a, b[0]) {
+        return (b[0], b[0], 0.0);
+    }
+    if contains(b, a[0]) {
+        return (a[0], a[0], 0.0);
+    }
+    let mut closest = (a[0], b[0], f32::INFINITY);
+    for (a0, a1) in edges(a) {
+        for (b0, b1) in edges(b) {
+            let candidate = segment_closest(a0, a1, b0, b1);
+            if candidate.2 < closest.2 {
+                closest = candidate;
+            }
+        }
+    }
+    closest
+}
+
+/// separating axis test over the sides of both cores, as the direction to push `b` in and how
+/// far
+fn least_overlap(a: &[[f32; 2]], b: &[[f32; 2]]) -> Option<([f32; 2], f32)> {
+    let mut least: Option<([f32; 2], f32)> = None;
+    for (p0, p1) in edges(a).chain(edges(b)) {
+        let side = sub(p1, p0);
+        let side_length = length(side);
+        if side_length == 0.0 {
+            continue;
+        }
+        let axis = [-side[1] / side_length, side[0] / side_length];
+        let (a_min, a_max) = project(a, axis);
+        let (b_min, b_max) = project(b, axis);
+        let candidate = if a_max - b_min < b_max - a_min {
+            (axis, a_max - b_min)
+        } else {
+            (scale(axis, -1.0), b_max - a_min)
+        };
+        if least.map_or(true, |(_, overlap)| candidate.1 < overlap) {
+            least = Some(candidate);
+        }
+    }
+    least
+}
+
+/// entry distance and normal of a ray against a core grown by `radius`, which is the core itself,
+/// a circle around each of its points and a box along each of its sides
+fn ray_rounded(
+    origin: [f32; 2],
+    direction: [f32; 2],
+    core: &[[f32; 2]],
+    radius: f32,
+) -> Option<(f32, [f32; 2])> {
+    let mut closest = if core.len() >= 3 {
+        ray_polygon(origin, direction, core)
+    } else {
+        None
+    };
+    if radius <= 0.0 {
+        return closest;
+    }
+    let mut keep = |hit: Option<(f32, [f32; 2])>| {
+        if let Some(hit) = hit {
+            if closest.map_or(true, |x| hit.0 < x.0) {
+                closest = Some(hit);
+            }
+        }
+    };
+    for point in core {
+        keep(ray_circle(origin, direction, *point, radius));
+    }
+    for (a, b) in edges(core) {
+        let side = sub(b, a);
+        let side_length = length(side);
+        // without a length there's no box, just the circles
+        if side_length == 0.0 {
+            continue;
+        }
+        let offset = scale([-side[1], side[0]], radius / side_length);
+        let body = [
+            add(a, offset),
+            add(b, offset),
+            sub(b, offset),
+            sub(a, offset),
+        ];
+        keep(ray_polygon(origin, direction, &body));
+    }
+    closest
+}
+
+/// the smallest convex polygon around the points, fewer than 3 points when they all sit on a line
+fn convex_hull(mut points: Vec<[f32; 2]>) -> Vec<[f32; 2]> {
+    points.sort_unstable_by(|x, y| x[0].total_cmp(&y[0]).then(x[1].total_cmp(&y[1])));
+    points.dedup();
+    if points.len() < 3 {
+        return points;
+    }
+    // lower half then upper half, dropping every point that doesn't turn left
+    let mut hull: Vec<[f32; 2]> = Vec::with_capacity(points.len() * 2);
+    for pass in 0..2 {
+        let start = hull.len();
+        for each in 0..points.len() {
+            let point = if pass == 0 {
+                points[each]
+            } else {
+                points[points.len() - 1 - each]
+            };
+            while hull.len() >= start + 2
+                && cross(
+                    sub(hull[hull.len() - 1], hull[hull.len() - 2]),
+                    sub(point, hull[hull.len() - 1]),
+                ) <= 0.0
+            {
+                hull.pop();
+            }
+            hull.push(point);
+        }
+        // the last point starts the other half
+        hull.pop();
+    }
+    hull
+}
+
+/// entry distance and normal of a ray against a circle
+fn ray_circle(
+    origin: [f32; 2],
+    direction: [f32; 2],
+    center: [f32; 2],
+    radius: f32,
+) -> Option<(f32, [f32; 2])> {
+    // a bare point has nothing to hit
+    if radius <= 0.0 {
+        return None;
+    }
+    let from_center = sub(origin, center);
+    let along = dot(from_center, direction);
+    let outside = dot(from_center, from_center) - radius * radius;
+    if outside <= 0.0 {
+        return Some((0.0, scale(direction, -1.0)));
+    }
+    let discriminant = along * along - outside;
+    if discriminant < 0.0 {
+        return None;
+    }
+    let distance = -along - discriminant.sqrt();
+    if distance < 0.0 {
+        return None;
+    }
+    let normal = sub(add(origin, scale(direction, distance)), center);
+    Some((distance, scale(normal, 1.0 / radius)))
+}
+
+/// entry distance and normal of a ray against a convex polygon, clipping the ray by each side,
+/// `None` for a polygon whose sides all have no length
+fn ray_polygon(
+    origin: [f32; 2],
+    direction: [f32; 2],
+    polygon: &[[f32; 2]],
+) -> Option<(f32, [f32; 2])> {
+    // outward normals depend on the winding
+    let mut area = 0.0;
+    for (a, b) in edges(polygon) {
+        area += cross(a, b);
+    }
+    let winding = if area < 0.0 { -1.0 } else { 1.0 };
+
+    let mut enter = 0.0;
+    let mut exit = f32::INFINITY;
+    let mut normal = scale(direction, -1.0);
+    let mut any_side = false;
+    for (a, b) in edges(polygon) {
+        let side = sub(b, a);
+        let side_length = length(side);
+        if side_length == 0.0 {
+            continue;
+        }
+        any_side = true;
+        let outward = scale([side[1], -side[0]], winding / side_length);
+        let facing = dot(outward, direction);
+        let room = dot(outward, sub(a, origin));
+        if facing == 0.0 {
+            if room < 0.0 {
+                return None;
+            }
+            continue;
+        }
+        let distance = room / facing;
+        if facing < 0.0 {
+            if distance > enter {
+                enter = distance;
+                normal = outward;
+            }
+        } else {
+            exit = exit.min(distance);
+        }
+        if enter > exit {
+            return None;
+        }
+    }
+    any_side.then_some((enter, normal))
+}
+
+fn project(points: &[[f32; 2]], axis: [f32; 2]) -> (f32, f32) {
+    points
+        .iter()
+        .fold((f32::INFINITY, f32::NEG_INFINITY), |(min, max), point| {
+            let along = dot(*point, axis);
+            (min.min(along), max.max(along))
+        })
+}
+
+/// the point furthest along `direction`, the middle of them when a whole side is
+fn support(points: &[[f32; 2]], direction: [f32; 2]) -> [f32; 2] {
+    let furthest = project(points, direction).1;
+    let mut sum = [0.0; 2];
+    let mut count = 0.0;
+    for point in points {
+        if furthest - dot(*point, direction) <= 1e-4 {
+            sum = add(sum, *point);
+            count += 1.0;
+        }
+    }
+    scale(sum, 1.0 / count)
+}
+
+/// the sides of a core, a point is one degenerate side and a segment a single one
+fn edges(points: &[[f32; 2]]) -> impl Iterator<Item = ([f32; 2], [f32; 2])> + '_ {
+    let count = if points.len() < 3 { 1 } else { points.len() };
+    (0..count).map(move |each| (points[each], points[(each + 1) % points.len()]))
+}
+
+/// whether `point` is inside a convex polygon, points and segments contain nothing
+fn contains(polygon: &[[f32; 2]], point: [f32; 2]) -> bool {
+    if polygon.len() < 3 {
+        return false;
+    }
+    let mut sign = 0.0f32;
+    for (a, b) in edges(polygon) {
+        let side = cross(sub(b, a), sub(point, a));
+        if side * sign < 0.0 {
+            return false;
+        }
+        if side != 0.0 {
+            sign = side;
+        }
+    }
+    true
+}
+
+/// the closest point on each segment and their distance
+fn segment_closest(
+    a0: [f32; 2],
+    a1: [f32; 2],
+    b0: [f32; 2],
+    b1: [f32; 2],
+) -> ([f32; 2], [f32; 2], f32) {
+    if segments_cross(a0, a1, b0, b1) {
+        return (a0, a0, 0.0);
+    }
+    let candidates = [
+        (a0, closest_on_segment(a0, b0, b1)),
+        (a1, closest_on_segment(a1, b0, b1)),
+        (closest_on_segment(b0, a0, a1), b0),
+        (closest_on_segment(b1, a0, a1), b1),
+    ];
+    let mut closest = (a0, b0, f32::INFINITY);
+    for (on_a, on_b) in candidates {
+        let distance = length(sub(on_b, on_a));
+        if distance < closest.2 {
+            closest = (on_a, on_b, distance);
+        }
+    }
+    closest
+}
+
+fn segments_cross(a0: [f32; 2], a1: [f32; 2], b0: [f32; 2], b1: [f32; 2]) -> bool {
+    let d1 = cross(sub(a1, a0), sub(b0, a0));
+    let d2 = cross(sub(a1, a0), sub(b1, a0));
+    let d3 = cross(sub(b1, b0), sub(a0, b0));
+    let d4 = cross(sub(b1, b0), sub(a1, b0));
+    d1 * d2 < 0.0 && d3 * d4 < 0.0
+}
+
+fn closest_on_segment(point: [f32; 2], a: [f32; 2], b: [f32; 2]) -> [f32; 2] {
+    let ab = sub(b, a);
+    let length_squared = dot(ab, ab);
+    if length_squared == 0.0 {
+        return a;
+    }
+    let t = (dot(sub(point, a), ab) / length_squared).clamp(0.0, 1.0);
+    add(a, [ab[0] * t, ab[1] * t])
+}
+
+fn rotate(v: [f32; 2], rotation: f32) -> [f32; 2] {
+    if rotation == 0.0 {
+        return v;
+    }
+    let (sin, cos) = rotation.sin_cos();
+    [v[0] * cos - v[1] * sin, v[0] * sin + v[1] * cos]
+}
+
+fn add(a: [f32; 2], b: [f32; 2]) -> [f32; 2] {
+    [a[0] + b[0], a[1] + b[1]]
+}
+
+fn sub(a: [f32; 2], b: [f32; 2]) -> [f32; 2] {
+    [a[0] - b[0], a[1] - b[1]]
+}
+
+fn scale(v: [f32; 2], by: f32) -> [f32; 2] {
+    [v[0] * by, v[1] * by]
+}
+
+fn dot(a: [f32; 2], b: [f32; 2]) -> f32 {
+    a[0] * b[0] + a[1] * b[1]
+}
+
+fn cross(a: [f32; 2], b: [f32; 2]) -> f32 {
+    a[0] * b[1] - a[1] * b[0]
+}
+
+fn length(v: [f32; 2]) -> f32 {
+    dot(v, v).sqrt()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close(a: f32, b: f32) -> bool {
+        (a - b).abs() < 1e-4
+    }
+
+    fn assert_near(actual: (f32, f32), expected: (f32, f32)) {
+        assert!(
+            close(actual.0, expected.0) && close(actual.1, expected.1),
+            "{:?} isn't {:?}",
+            actual,
+            expected
+        );
+    }
+
+    fn circle(center: [f32; 2], radius: f32) -> Placed {
+        Placed::new(&CollisionShape::Circle { radius }, center, [radius; 2])
+    }
+
+    fn square(center: [f32; 2], half_size: f32) -> Placed {
+        Placed::new(&CollisionShape::Rect, center, [half_size; 2])
+    }
+
+    fn capsule(center: [f32; 2], half_length: f32, radius: f32, rotation: f32) -> Placed {
+        let shape = CollisionShape::Capsule {
+            half_length,
+            radius,
+            rotation,
+        };
+        Placed::new(&shape, center, [half_length + radius, radius])
+    }
+
+    #[test]
+    fn circles_separate_touch_and_overlap() {
+        let first = circle([0.0, 0.0], 1.0);
+        assert_eq!(first.contact(&circle([3.0, 0.0], 1.0)), None);
+
+        let touching = first.contact(&circle([2.0, 0.0], 1.0)).unwrap();
+        assert_near(touching.normal, (1.0, 0.0));
+        assert!(close(touching.depth, 0.0));
+        assert_near(touching.point, (1.0, 0.0));
+
+        let overlapping = first.contact(&circle([0.0, 1.5], 1.0)).unwrap();
+        assert_near(overlapping.normal, (0.0, 1.0));
+        assert!(close(overlapping.depth, 0.5));
+        assert_near(overlapping.point, (0.0, 0.75));
+    }
+
+    #[test]
+    fn boxes_push_apart_along_the_shallower_axis() {
+        let first = square([0.0, 0.0], 1.0);
+        assert_eq!(first.contact(&square([2.5, 0.0], 1.0)), None);
+
+        let touching = first.contact(&square([2.0, 0.5], 1.0)).unwrap();
+        assert!(close(touching.depth, 0.0));
+        assert_near(touching.normal, (1.0, 0.0));
+
+        let second = square([1.5, 0.2], 1.0);
+        let contact = first.contact(&second).unwrap();
+        assert_near(contact.normal, (1.0, 0.0));
+        assert!(close(contact.depth, 0.5));
+        // seen from the other box the normal turns around
+        let contact = second.contact(&first).unwrap();
+        assert_near(contact.normal, (-1.0, 0.0));
+        assert!(close(contact.depth, 0.5));
+
+        let contact = first.contact(&square([-0.2, -1.7], 1.0)).unwrap();
+        assert_near(contact.normal, (0.0, -1.0));
+        assert!(close(contact.depth, 0.3));
+    }
+
+    #[test]
+    fn circle_inside_a_box_leaves_through_the_nearest_side() {
+        let contact = square([0.0, 0.0], 1.0)
+            .contact(&circle([0.5, 0.0], 0.25))
+            .unwrap();
+        assert_near(contact.normal, (1.0, 0.0));
+        assert!(close(contact.depth, 0.75));
+
+        // two points on top of each other have no side to leave through
+        let contact = circle([1.0, 1.0], 0.5)
+            .contact(&circle([1.0, 1.0], 0.5))
+            .unwrap();
+        assert_near(contact.normal, (0.0, 1.0));
+        assert!(close(contact.depth, 1.0));
+    }
+
+    #[test]
+    fn capsules_touch_along_their_segment() {
+        let lying = capsule([0.0, 0.0], 2.0, 0.5, 0.0);
+        assert_eq!(lying.contact(&circle([1.5, 1.6], 1.0)), None);
+        let contact = lying.contact(&circle([1.5, 1.2], 1.0)).unwrap();
+        assert_near(contact.normal, (0.0, 1.0));
+        assert!(close(contact.depth, 0.3));
+
+        // stood up it only reaches the circle past its end
+        let standing = capsule([0.0, 0.0], 2.0, 0.5, std::f32::consts::FRAC_PI_2);
+        assert_eq!(standing.contact(&circle([1.5, 1.2], 0.9)), None);
+        let contact = standing.contact(&circle([0.0, 3.0], 1.0)).unwrap();
+        assert_near(contact.normal, (0.0, 1.0));
+        assert!(close(contact.depth, 0.5));
+    }
+
+    #[test]
+    fn closest_points_between_cores() {
+        let (on_a, on_b, distance) =
+            closest_points(&[[0.0, 0.0], [1.0, 0.0]], &[[3.0, -1.0], [3.0, 1.0]]);
+        assert_eq!((on_a, on_b), ([1.0, 0.0], [3.0, 0.0]));
+        assert!(close(distance, 2.0));
+
+        let a = [[0.0, 0.0], [2.0, 0.0], [2.0, 2.0], [0.0, 2.0]];
+        let (_, _, distance) = closest_points(&a, &[[5.0, 1.0]]);
+        assert!(close(distance, 3.0));
+        // a point inside or a crossing side is no distance at all
+        assert_eq!(closest_points(&a, &[[1.0, 1.0]]).2, 0.0);
+        assert_eq!(closest_points(&a, &[[1.0, -1.0], [1.0, 3.0]]).2, 0.0);
+    }
+
+    #[test]
+    fn least_overlap_points_from_a_to_b() {
+        let a = [[0.0, 0.0], [2.0, 0.0], [2.0, 2.0], [0.0, 2.0]];
+        let shifted = |x: f32, y: f32| a.map(|point| [point[0] + x, point[1] + y]);
+
+        let (normal, overlap) = least_overlap(&a, &shifted(1.5, 0.5)).unwrap();
+        assert_near((normal[0], normal[1]), (1.0, 0.0));
+        assert!(close(overlap, 0.5));
+
+        let (normal, overlap) = least_overlap(&a, &shifted(0.2, -1.0)).unwrap();
+        assert_near((normal[0], normal[1]), (0.0, -1.0));
+        assert!(close(overlap, 1.0));
+
+        // touching is no overlap, apart is a negative one along the separating axis
+        let (normal, overlap) = least_overlap(&a, &shifted(-2.0, 0.0)).unwrap();
+        assert_near((normal[0], normal[1]), (-1.0, 0.0));
+        assert!(close(overlap, 0.0));
+        let (normal, overlap) = least_overlap(&a, &shifted(3.0, 0.5)).unwrap();
+        assert_near((normal[0], normal[1]), (1.0, 0.0));
+        assert!(close(overlap, -1.0));
+
+        // points and segments of no length have no sides
+        assert_eq!(least_overlap(&[[0.0, 0.0]], &[[0.0, 0.0]]), None);
+    }
+    fn polygon(center: [f32; 2], points: &[(f32, f32)]) -> Placed {
+        let shape = CollisionShape::Polygon(ConvexPolygon::new(points).unwrap());
+        Placed::new(&shape, center, [1.0, 1.0])
+    }
+
+    fn assert_hit(hit: Option<(f32, [f32; 2])>, distance: f32, normal: (f32, f32)) {
+        let (actual, actual_normal) = hit.expect("the ray should hit");
+        assert!(
+            close(actual, distance),
+            "hit at {} not {}",
+            actual,
+            distance
+        );
+        assert_near((actual_normal[0], actual_normal[1]), normal);
+    }
+
+    #[test]
+    fn rays_hit_circles_on_their_outline() {
+        let target = circle([5.0, 0.0], 1.0);
+        assert_hit(
+            target.raycast([0.0, 0.0], [1.0, 0.0], 10.0),
+            4.0,
+            (-1.0, 0.0),
+        );
+        assert_hit(
+            target.raycast([5.0, 3.0], [0.0, -1.0], 10.0),
+            2.0,
+            (0.0, 1.0),
+        );
+        assert_eq!(target.raycast([0.0, 0.0], [1.0, 0.0], 3.9), None);
+        assert_eq!(target.raycast([0.0, 0.0], [0.0, 1.0], 10.0), None);
+        assert_eq!(target.raycast([0.0, 0.0], [-1.0, 0.0], 10.0), None);
+        // starting inside hits right away, the normal facing the ray
+        assert_hit(
+            target.raycast([5.5, 0.0], [0.0, 1.0], 10.0),
+            0.0,
+            (0.0, -1.0),
+        );
+    }
+
+    #[test]
+    fn rays_hit_capsules_on_their_body_and_caps() {
+        let target = capsule([0.0, 5.0], 2.0, 0.5, 0.0);
+        assert_hit(
+            target.raycast([1.0, 0.0], [0.0, 1.0], 10.0),
+            4.5,
+            (0.0, -1.0),
+        );
+        assert_hit(
+            target.raycast([5.0, 5.0], [-1.0, 0.0], 10.0),
+            2.5,
+            (1.0, 0.0),
+        );
+        // just past the end of the segment only the round cap is left
+        let hit = target.raycast([2.3, 0.0], [0.0, 1.0], 10.0);
+        assert_hit(hit, 5.0 - 0.4, (0.6, -0.8));
+        assert_eq!(target.raycast([2.6, 0.0], [0.0, 1.0], 10.0), None);
+        assert_hit(
+            target.raycast([-1.0, 5.0], [1.0, 0.0], 10.0),
+            0.0,
+            (-1.0, 0.0),
+        );
+    }
+
+    #[test]
+    fn rays_hit_polygons_of_either_winding() {
+        let counter_clockwise = [(-1.0, -1.0), (1.0, -1.0), (0.0, 1.0)];
+        let clockwise = [(-1.0, -1.0), (0.0, 1.0), (1.0, -1.0)];
+        for points in [&counter_clockwise, &clockwise] {
+            let target = polygon([0.0, 5.0], points);
+            assert_hit(
+                target.raycast([0.0, 0.0], [0.0, 1.0], 10.0),
+                4.0,
+                (0.0, -1.0),
+            );
+            assert_hit(
+                target.raycast([0.5, 10.0], [0.0, -1.0], 10.0),
+                5.0,
+                (2.0 / 5f32.sqrt(), 1.0 / 5f32.sqrt()),
+            );
+            assert_eq!(target.raycast([2.0, 0.0], [0.0, 1.0], 10.0), None);
+            assert_hit(
+                target.raycast([0.0, 5.0], [1.0, 0.0], 10.0),
+                0.0,
+                (-1.0, 0.0),
+            );
+        }
+
+        let target = square([5.0, 4.0], 1.0);
+        let diagonal = [0.5f32.sqrt(); 2];
+        assert_hit(
+            target.raycast([0.0, 0.0], diagonal, 10.0),
+            4.0 * 2f32.sqrt(),
+            (-1.0, 0.0),
+        );
+    }
+
+    #[test]
+    fn degenerate_shapes_are_never_hit() {
+        // all of them sit on the ray origin, which used to count as a hit from inside
+        let point = circle([0.0, 0.0], 0.0);
+        let segment = capsule([0.0, 0.0], 0.0, 0.0, 0.0);
+        let flat_box = square([0.0, 0.0], 0.0);
+        for shape in [point, segment, flat_box] {
+            assert_eq!(shape.raycast([0.0, 0.0], [1.0, 0.0], 10.0), None);
+            assert_eq!(shape.raycast([-1.0, 0.0], [1.0, 0.0], 10.0), None);
+        }
+        assert_eq!(ray_polygon([0.0, 0.0], [1.0, 0.0], &[[0.0, 0.0]; 3]), None);
+        assert_eq!(ray_circle([0.0, 0.0], [1.0, 0.0], [0.0, 0.0], 0.0), None);
+
+        // a capsule of no length is still its circle
+        let ball = capsule([3.0, 0.0], 0.0, 1.0, 0.0);
+        assert_hit(ball.raycast([0.0, 0.0], [1.0, 0.0], 10.0), 2.0, (-1.0, 0.0));
+    }
+
+    #[test]
+    fn casts_stop_where_the_shapes_touch() {
+        let moving = circle([0.0, 0.0], 1.0);
+        let (distance, contact) = moving
+            .cast([1.0, 0.0], 10.0, &circle([5.0, 0.0], 1.0))
+            .unwrap();
+        assert!(close(distance, 3.0));
+        assert_near(contact.normal, (1.0, 0.0));
+        assert_near(contact.point, (4.0, 0.0));
+        assert_eq!(contact.depth, 0.0);
+        assert_eq!(moving.cast([1.0, 0.0], 2.9, &circle([5.0, 0.0], 1.0)), None);
+        assert_eq!(
+            moving.cast([0.0, 1.0], 10.0, &circle([5.0, 0.0], 1.0)),
+            None
+        );
+
+        // a box falling on a box lands flat, whatever the sideways offset
+        let falling = square([0.3, 5.0], 1.0);
+        let (distance, contact) = falling
+            .cast([0.0, -1.0], 10.0, &square([0.0, 0.0], 1.0))
+            .unwrap();
+        assert!(close(distance, 3.0));
+        assert_near(contact.normal, (0.0, -1.0));
+
+        let (distance, contact) = capsule([-6.0, 0.5], 1.0, 0.5, 0.0)
+            .cast([1.0, 0.0], 10.0, &square([0.0, 0.0], 1.0))
+            .unwrap();
+        assert!(close(distance, 3.5));
+        assert_near(contact.normal, (1.0, 0.0));
+        assert_near(contact.point, (-1.0, 0.5));
+
+        // already touching doesn't move at all
+        let (distance, contact) = moving
+            .cast([1.0, 0.0], 10.0, &circle([1.5, 0.0], 1.0))
+            .unwrap();
+        assert_eq!(distance, 0.0);
+        assert!(close(contact.depth, 0.5));
+    }
+
+    #[test]
+    fn convex_hull_winds_counter_clockwise_without_inner_points() {
+        let points = vec![
+            [0.0, 0.0],
+            [2.0, 2.0],
+            [1.0, 1.0],
+            [2.0, 0.0],
+            [1.0, 0.0],
+            [0.0, 2.0],
+            [2.0, 2.0],
+        ];
+        let hull = convex_hull(points);
+        assert_eq!(hull, [[0.0, 0.0], [2.0, 0.0], [2.0, 2.0], [0.0, 2.0]]);
+        let area: f32 = edges(&hull).map(|(a, b)| cross(a, b)).sum();
+        assert!(close(area, 8.0));
+
+        // on a line it falls back to the ends and the points it was given
+        assert_eq!(
+            convex_hull(vec![[0.0, 0.0], [1.0, 1.0], [2.0, 2.0]]).len(),
+            2
+        );
+        assert_eq!(convex_hull(vec![[1.0, 1.0], [1.0, 1.0]]), [[1.0, 1.0]]);
+    }
+}
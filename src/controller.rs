@@ -0,0 +1,485 @@
+//! declarative animation state graphs, game code only sets parameters and the renderer switches
+//! the textures
+//!
+//! a controller file maps named states to texture entries and lists the transitions between them,
+//! transitions are tried in order and the first one that passes is taken, at most one per frame
+//!
+//! ```json
+//! {
+//!     "initial": "idle",
+//!     "states": { "idle": { "texture": "player_idle" }, "run": { "texture": "player_run" } },
+//!     "transitions": [
+//!         { "from": "idle", "to": "run", "conditions": [{ "param": "speed", "greater": 0.1 }] },
+//!         { "from": "run", "to": "idle", "conditions": [{ "param": "speed", "less": 0.1 }], "duration": 0.1 },
+//!         { "from": "*", "to": "idle", "on_cycle_end": true, "conditions": [{ "param": "attacking", "is": false }] }
+//!     ]
+//! }
+//! ```
+
+use std::{collections::HashMap, path::Path};
+
+use crate::{RendererError, TextureId};
+
+/// a state transition can start from any state with this as `from`
+pub const ANY_STATE: &str = "*";
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq)]
+pub struct AnimationController {
+    pub initial: String,
+    pub states: HashMap<String, ControllerState>,
+    #[serde(default)]
+    pub transitions: Vec<Transition>,
+}
+impl AnimationController {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, RendererError> {
+        let path = path.as_ref();
+        let val = std::fs::read(path).map_err(|err| RendererError::AssetIo {
+            path: path.to_path_buf(),
+            source: err,
+        })?;
+        serde_json::from_slice(&val).map_err(|err| RendererError::AssetDecode {
+            path: path.to_path_buf(),
+            source: Box::new(err),
+        })
+    }
+
+    /// every state a transition or `initial` names has to exist, and every texture a state plays
+    fn validate(
+        &self,
+        name: &str,
+        textures: &HashMap<String, TextureId>,
+    ) -> Result<(), RendererError> {
+        let invalid = |reason| RendererError::InvalidController {
+            name: name.to_string(),
+            reason,
+        };
+        for state in self.states.values() {
+            if !textures.contains_key(&state.texture) {
+                return Err(RendererError::UnknownTexture(state.texture.clone()));
+            }
+        }
+        if !self.states.contains_key(&self.initial) {
+            return Err(invalid("initial state isn't defined"));
+        }
+        for transition in &self.transitions {
+            if transition.from != ANY_STATE && !self.states.contains_key(&transition.from) {
+                return Err(invalid("transition from an undefined state"));
+            }
+            if !self.states.contains_key(&transition.to) {
+                return Err(invalid("transition to an undefined state"));
+            }
+        }
+        Ok(())
+    }
+
+    /// turns the names into indices once, only valid after `validate` passed
+    fn resolve(&self, textures: &HashMap<String, TextureId>) -> Resolved {
+        // sorted so the indices don't change between runs
+        let mut state_names: Vec<_> = self.states.keys().cloned().collect();
+        state_names.sort_unstable();
+        let index = |state: &str| state_names.iter().position(|x| x == state).unwrap();
+        let transitions = self
+            .transitions
+            .iter()
+            .map(|transition| ResolvedTransition {
+                from: (transition.from != ANY_STATE).then(|| index(&transition.from)),
+                to: index(&transition.to),
+                conditions: transition.conditions.clone(),
+                on_cycle_end: transition.on_cycle_end,
+                duration: transition.duration,
+            })
+            .collect();
+        Resolved {
+            initial: index(&self.initial),
+            textures: state_names
+                .iter()
+                .map(|state| textures[&self.states[state].texture])
+                .collect(),
+            transitions,
+            state_names,
+            source: self.clone(),
+        }
+    }
+}
+
+/// a controller with states as indices and textures as ids, so evaluating it looks nothing up
+/// by name
+#[derive(Debug)]
+struct Resolved {
+    source: AnimationController,
+    state_names: Vec<String>,
+    // texture of each state
+    textures: Vec<TextureId>,
+    initial: usize,
+    transitions: Vec<ResolvedTransition>,
+}
+
+#[derive(Debug)]
+struct ResolvedTransition {
+    /// `None` for any state
+    from: Option<usize>,
+    to: usize,
+    conditions: Vec<Condition>,
+    on_cycle_end: bool,
+    duration: f32,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq)]
+pub struct ControllerState {
+    /// entry of the texture description file played in this state
+    pub texture: String,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq)]
+pub struct Transition {
+    /// a state name or `*` for any state but `to` itself
+    pub from: String,
+    pub to: String,
+    /// all of them have to hold
+    #[serde(default)]
+    pub conditions: Vec<Condition>,
+    /// only taken once the current animation finished a cycle, or reached its last frame when
+    /// it doesn't loop
+    #[serde(default)]
+    pub on_cycle_end: bool,
+    /// crossfade length in seconds, 0 switches instantly
+    #[serde(default)]
+    pub duration: f32,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq)]
+pub struct Condition {
+    pub param: String,
+    #[serde(flatten)]
+    pub test: Test,
+}
+
+/// parameters that were never set count as 0 and false
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum Test {
+    Greater(f32),
+    Less(f32),
+    Is(bool),
+}
+impl Condition {
+    fn holds(&self, params: &HashMap<String, Param>) -> bool {
+        let param = params.get(&self.param).copied();
+        match (self.test, param) {
+            (Test::Greater(x), Some(Param::Float(value))) => value > x,
+            (Test::Greater(x), None) => 0.0 > x,
+            (Test::Less(x), Some(Param::Float(value))) => value < x,
+            (Test::Less(x), None) => 0.0 < x,
+            (Test::Is(x), Some(Param::Bool(value))) => value == x,
+            (Test::Is(x), None) => !x,
+            _ => false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Param {
+    Float(f32),
+    Bool(bool),
+}
+
+#[derive(Debug)]
+struct Instance {
+    controller: usize,
+    /// `None` until the renderer put the sprite into the initial state
+    state: Option<usize>,
+    params: HashMap<String, Param>,
+}
+
+/// read on state, holds the controller assets and which sprite runs which of them
+///
+/// sprites are keyed by their sparse index, removing a sprite through the sprite master detaches it
+#[derive(Debug)]
+pub struct AnimationControllers {
+    // texture entries the states may play
+    textures: HashMap<String, TextureId>,
+    assets: Vec<Resolved>,
+    asset_ids: HashMap<String, usize>,
+    instances: HashMap<usize, Instance>,
+}
+impl AnimationControllers {
+    pub(crate) fn new(textures: HashMap<String, TextureId>) -> Self {
+        Self {
+            textures,
+            assets: vec![],
+            asset_ids: HashMap::new(),
+            instances: HashMap::new(),
+        }
+    }
+
+    /// a name can only be added once, so attached sprites never change controller underneath
+    pub fn add(
+        &mut self,
+        name: impl Into<String>,
+        controller: AnimationController,
+    ) -> Result<(), RendererError> {
+        let name = name.into();
+        if self.asset_ids.contains_key(&name) {
+            return Err(RendererError::InvalidController {
+                name,
+                reason: "a controller of this name was already added",
+            });
+        }
+        controller.validate(&name, &self.textures)?;
+        self.asset_ids.insert(name, self.assets.len());
+        self.assets.push(controller.resolve(&self.textures));
+        Ok(())
+    }
+
+    pub fn get(&self, name: &str) -> Option<&AnimationController> {
+        Some(&self.assets[*self.asset_ids.get(name)?].source)
+    }
+
+    /// the sprite enters the initial state on the next frame, attaching again restarts it
+    pub fn attach(&mut self, sparse_index: usize, controller: &str) -> Result<(), RendererError> {
+        let Some(&controller) = self.asset_ids.get(controller) else {
+            return Err(RendererError::UnknownController(controller.to_string()));
+        };
+        self.instances.insert(
+            sparse_index,
+            Instance {
+                controller,
+                state: None,
+                params: HashMap::new(),
+            },
+        );
+        Ok(())
+    }
+
+    pub fn detach(&mut self, sparse_index: usize) {
+        self.instances.remove(&sparse_index);
+    }
+
+    pub fn set_float(&mut self, sparse_index: usize, param: &str, value: f32) {
+        self.set(sparse_index, param, Param::Float(value));
+    }
+
+    pub fn set_bool(&mut self, sparse_index: usize, param: &str, value: bool) {
+        self.set(sparse_index, param, Param::Bool(value));
+    }
+
+    /// does nothing for sprites without a controller
+    pub fn set(&mut self, sparse_index: usize, param: &str, value: Param) {
+        if let Some(instance) = self.instances.get_mut(&sparse_index) {
+            instance.params.insert(param.to_string(), value);
+        }
+    }
+
+    pub fn param(&self, sparse_index: usize, param: &str) -> Option<Param> {
+        self.instances
+            .get(&sparse_index)?
+            .params
+            .get(param)
+            .copied()
+    }
+
+    pub fn state(&self, sparse_index: usize) -> Option<&str> {
+        let instance = self.instances.get(&sparse_index)?;
+        Some(&self.assets[instance.controller].state_names[instance.state?])
+    }
+
+    /// picks the state the sprite should move to this frame, as the texture to play and the
+    /// crossfade length
+    pub(crate) fn evaluate(
+        &mut self,
+        sparse_index: usize,
+        cycle_finished: bool,
+    ) -> Option<(TextureId, f32)> {
+        let instance = self.instances.get_mut(&sparse_index)?;
+        let controller = &self.assets[instance.controller];
+        let Some(current) = instance.state else {
+            instance.state = Some(controller.initial);
+            return Some((controller.textures[controller.initial], 0.0));
+        };
+        let transition = controller.transitions.iter().find(|transition| {
+            (transition.from == Some(current)
+                || (transition.from.is_none() && transition.to != current))
+                && (!transition.on_cycle_end || cycle_finished)
+                && transition
+                    .conditions
+                    .iter()
+                    .all(|condition| condition.holds(&instance.params))
+        })?;
+        instance.state = Some(transition.to);
+        Some((controller.textures[transition.to], transition.duration))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn textures() -> HashMap<String, TextureId> {
+        ["player_idle", "player_run", "player_attack"]
+            .into_iter()
+            .enumerate()
+            .map(|(id, name)| (name.to_string(), TextureId(id as u32)))
+            .collect()
+    }
+
+    fn controller(json: &str) -> AnimationController {
+        serde_json::from_str(json).unwrap()
+    }
+
+    /// idle and run switch on `speed`, any state goes to attack on `attacking` and attack only
+    /// leaves once its cycle ended
+    fn player() -> AnimationControllers {
+        let mut controllers = AnimationControllers::new(textures());
+        controllers
+            .add(
+                "player",
+                controller(
+                    r#"{
+                        "initial": "idle",
+                        "states": {
+                            "idle": { "texture": "player_idle" },
+                            "run": { "texture": "player_run" },
+                            "attack": { "texture": "player_attack" }
+                        },
+                        "transitions": [
+                            { "from": "idle", "to": "run", "conditions": [{ "param": "speed", "greater": 0.1 }], "duration": 0.2 },
+                            { "from": "run", "to": "idle", "conditions": [{ "param": "speed", "less": 0.1 }] },
+                            { "from": "*", "to": "attack", "conditions": [{ "param": "attacking", "is": true }] },
+                            { "from": "attack", "to": "idle", "on_cycle_end": true }
+                        ]
+                    }"#,
+                ),
+            )
+            .unwrap();
+        controllers.attach(0, "player").unwrap();
+        controllers
+    }
+
+    #[test]
+    fn validate_rejects_unknown_states_and_textures() {
+        let check = |json| controller(json).validate("test", &textures());
+        assert!(check(
+            r#"{ "initial": "idle", "states": { "idle": { "texture": "player_idle" } } }"#
+        )
+        .is_ok());
+        assert!(matches!(
+            check(r#"{ "initial": "idle", "states": { "idle": { "texture": "missing" } } }"#),
+            Err(RendererError::UnknownTexture(texture)) if texture == "missing"
+        ));
+        assert!(matches!(
+            check(r#"{ "initial": "run", "states": { "idle": { "texture": "player_idle" } } }"#),
+            Err(RendererError::InvalidController { .. })
+        ));
+        assert!(matches!(
+            check(
+                r#"{ "initial": "idle", "states": { "idle": { "texture": "player_idle" } },
+                    "transitions": [{ "from": "run", "to": "idle" }] }"#
+            ),
+            Err(RendererError::InvalidController { .. })
+        ));
+        assert!(matches!(
+            check(
+                r#"{ "initial": "idle", "states": { "idle": { "texture": "player_idle" } },
+                    "transitions": [{ "from": "idle", "to": "run" }] }"#
+            ),
+            Err(RendererError::InvalidController { .. })
+        ));
+        assert!(check(
+            r#"{ "initial": "idle", "states": { "idle": { "texture": "player_idle" } },
+                "transitions": [{ "from": "*", "to": "idle" }] }"#
+        )
+        .is_ok());
+    }
+
+    #[test]
+    fn unset_params_count_as_zero_and_false() {
+        let holds = |test, params: &HashMap<String, Param>| {
+            Condition {
+                param: "x".to_string(),
+                test,
+            }
+            .holds(params)
+        };
+        let unset = HashMap::new();
+        assert!(holds(Test::Greater(-1.0), &unset));
+        assert!(!holds(Test::Greater(0.0), &unset));
+        assert!(holds(Test::Less(1.0), &unset));
+        assert!(!holds(Test::Less(0.0), &unset));
+        assert!(holds(Test::Is(false), &unset));
+        assert!(!holds(Test::Is(true), &unset));
+
+        // a param of the other kind never passes
+        let set = HashMap::from([("x".to_string(), Param::Bool(true))]);
+        assert!(holds(Test::Is(true), &set));
+        assert!(!holds(Test::Greater(-1.0), &set));
+        assert!(!holds(Test::Less(1.0), &set));
+    }
+
+    #[test]
+    fn adding_a_name_twice_is_rejected() {
+        let mut controllers = player();
+        let other =
+            controller(r#"{ "initial": "run", "states": { "run": { "texture": "player_run" } } }"#);
+        assert!(matches!(
+            controllers.add("player", other),
+            Err(RendererError::InvalidController { .. })
+        ));
+        assert_eq!(controllers.get("player").unwrap().initial, "idle");
+        assert_eq!(controllers.evaluate(0, false), Some((TextureId(0), 0.0)));
+    }
+
+    #[test]
+    fn transitions_follow_the_params() {
+        let mut controllers = player();
+        assert_eq!(controllers.state(0), None);
+        assert_eq!(controllers.evaluate(0, false), Some((TextureId(0), 0.0)));
+        assert_eq!(controllers.state(0), Some("idle"));
+        assert_eq!(controllers.evaluate(0, false), None);
+
+        controllers.set_float(0, "speed", 1.0);
+        assert_eq!(controllers.evaluate(0, false), Some((TextureId(1), 0.2)));
+        assert_eq!(controllers.state(0), Some("run"));
+        controllers.set_float(0, "speed", 0.0);
+        assert_eq!(controllers.evaluate(0, false), Some((TextureId(0), 0.0)));
+        assert_eq!(controllers.state(0), Some("idle"));
+    }
+
+    #[test]
+    fn any_state_never_reenters_its_target() {
+        let mut controllers = player();
+        controllers.evaluate(0, false);
+        controllers.set_float(0, "speed", 1.0);
+        controllers.evaluate(0, false);
+        controllers.set_bool(0, "attacking", true);
+        assert_eq!(controllers.evaluate(0, false), Some((TextureId(2), 0.0)));
+        assert_eq!(controllers.state(0), Some("attack"));
+        assert_eq!(controllers.evaluate(0, false), None);
+        assert_eq!(controllers.state(0), Some("attack"));
+    }
+
+    #[test]
+    fn on_cycle_end_waits_for_the_cycle() {
+        let mut controllers = player();
+        controllers.evaluate(0, false);
+        controllers.set_bool(0, "attacking", true);
+        controllers.evaluate(0, false);
+        controllers.set_bool(0, "attacking", false);
+        assert_eq!(controllers.evaluate(0, false), None);
+        assert_eq!(controllers.state(0), Some("attack"));
+        assert_eq!(controllers.evaluate(0, true), Some((TextureId(0), 0.0)));
+        assert_eq!(controllers.state(0), Some("idle"));
+    }
+
+    #[test]
+    fn detached_sprites_are_left_alone() {
+        let mut controllers = player();
+        assert!(matches!(
+            controllers.attach(1, "enemy"),
+            Err(RendererError::UnknownController(_))
+        ));
+        controllers.detach(0);
+        controllers.set_float(0, "speed", 1.0);
+        assert_eq!(controllers.param(0, "speed"), None);
+        assert_eq!(controllers.evaluate(0, false), None);
+    }
+}
@@ -15,11 +15,162 @@ use std::{
     slice::from_raw_parts,
 };
 
+pub mod atlas;
+pub mod collision;
+pub mod controller;
+
+use collision::{
+    CollisionEvent, CollisionFilter, CollisionPhase, CollisionShape, FilterMode, Manifold,
+    QueryFilter, RayHit, ShapeHit,
+};
+use controller::AnimationControllers;
 pub use ecs;
 use ecs::Access;
 use rayon::{prelude::IntoParallelRefMutIterator, slice::ParallelSliceMut};
 pub use winit;
 
+/// every failure the renderer can report, asset and gpu errors keep their source around
+#[derive(Debug)]
+pub enum RendererError {
+    /// no entry of this name in the texture description file
+    UnknownTexture(String),
+    /// an entry of the description file that cannot be placed in the texture array
+    InvalidTextureDescription {
+        name: String,
+        reason: &'static str,
+    },
+    /// the atlas needs more layers than the device supports
+    TooManyAtlasPages {
+        pages: u32,
+        max: u32,
+    },
+    /// no animation controller of this name was added
+    UnknownController(String),
+    /// a controller whose transitions or initial state name states it doesn't have
+    InvalidController {
+        name: String,
+        reason: &'static str,
+    },
+    /// every animation slot is taken, holds the capacity
+    CapacityExhausted(u32),
+    /// the sprite's animation slot was freed or handed to another sprite, holds the slot
+    StaleSlot(u32),
+    /// a collision shape that can't be tested
+    InvalidShape(&'static str),
+    /// seeking past the frames the animation has
+    FrameOutOfRange {
+        frame: u32,
+        frames: u32,
+    },
+    AssetIo {
+        path: std::path::PathBuf,
+        source: std::io::Error,
+    },
+    AssetDecode {
+        path: std::path::PathBuf,
+        source: Box<dyn std::error::Error + Send + Sync>,
+    },
+    ImageSave {
+        path: std::path::PathBuf,
+        source: image::ImageError,
+    },
+    /// the surface needs to be configured again
+    SurfaceLost,
+    /// the surface no longer matches the window, usually after a resize
+    SurfaceOutdated,
+    Surface(wgpu::SurfaceError),
+    CreateSurface(wgpu::CreateSurfaceError),
+    RequestDevice(wgpu::RequestDeviceError),
+    NoAdapter,
+    Window(winit::error::OsError),
+    Config(ConfigError),
+    /// errors coming from the ecs table
+    Ecs(&'static str),
+}
+impl std::fmt::Display for RendererError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::UnknownTexture(name) => write!(f, "unknown texture name {:?}", name),
+            Self::InvalidTextureDescription { name, reason } => {
+                write!(f, "invalid texture description {:?}: {}", name, reason)
+            }
+            Self::TooManyAtlasPages { pages, max } => {
+                write!(
+                    f,
+                    "atlas needs {} pages but the device supports {}",
+                    pages, max
+                )
+            }
+            Self::UnknownController(name) => write!(f, "unknown animation controller {:?}", name),
+            Self::InvalidController { name, reason } => {
+                write!(f, "invalid animation controller {:?}: {}", name, reason)
+            }
+            Self::CapacityExhausted(capacity) => {
+                write!(f, "all {} animation slots are in use", capacity)
+            }
+            Self::StaleSlot(slot) => {
+                write!(f, "animation slot {} no longer belongs to the sprite", slot)
+            }
+            Self::InvalidShape(reason) => write!(f, "invalid collision shape: {}", reason),
+            Self::FrameOutOfRange { frame, frames } => {
+                write!(f, "frame {} is out of range for {} frames", frame, frames)
+            }
+            Self::AssetIo { path, source } => {
+                write!(f, "cannot read {}: {}", path.display(), source)
+            }
+            Self::AssetDecode { path, source } => {
+                write!(f, "cannot decode {}: {}", path.display(), source)
+            }
+            Self::ImageSave { path, source } => {
+                write!(f, "cannot save {}: {}", path.display(), source)
+            }
+            Self::SurfaceLost => write!(f, "surface lost"),
+            Self::SurfaceOutdated => write!(f, "surface outdated"),
+            Self::Surface(err) => write!(f, "surface error: {}", err),
+            Self::CreateSurface(err) => write!(f, "cannot create surface: {}", err),
+            Self::RequestDevice(err) => write!(f, "cannot request device: {}", err),
+            Self::NoAdapter => write!(f, "no suitable adapter found"),
+            Self::Window(err) => write!(f, "cannot create window: {}", err),
+            Self::Config(err) => write!(f, "invalid config: {}", err),
+            Self::Ecs(err) => write!(f, "ecs error: {}", err),
+        }
+    }
+}
+impl std::error::Error for RendererError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::AssetIo { source, .. } => Some(source),
+            Self::AssetDecode { source, .. } => Some(source.as_ref()),
+            Self::ImageSave { source, .. } => Some(source),
+            Self::Surface(err) => Some(err),
+            Self::CreateSurface(err) => Some(err),
+            Self::RequestDevice(err) => Some(err),
+            Self::Window(err) => Some(err),
+            Self::Config(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+impl From<&'static str> for RendererError {
+    fn from(err: &'static str) -> Self {
+        Self::Ecs(err)
+    }
+}
+impl From<ConfigError> for RendererError {
+    fn from(err: ConfigError) -> Self {
+        Self::Config(err)
+    }
+}
+impl From<wgpu::SurfaceError> for RendererError {
+    fn from(err: wgpu::SurfaceError) -> Self {
+        match err {
+            wgpu::SurfaceError::Lost => Self::SurfaceLost,
+            wgpu::SurfaceError::Outdated => Self::SurfaceOutdated,
+            err => Self::Surface(err),
+        }
+    }
+}
+
 #[repr(C)]
 #[derive(Debug, Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
 pub struct Animation {
@@ -31,6 +182,14 @@ pub struct Animation {
     pub cycle_just_finished: u32,
 
     pub actual_depth: f32,
+
+    // slot of the animation being faded out, `NO_SLOT` when not transitioning
+    fade_from: u32,
+    fade_elapsed: f32,
+    fade_duration: f32,
+
+    /// on the way back of a ping pong cycle
+    pub backwards: u32,
 }
 impl Animation {
     fn new_empty() -> Self {
@@ -42,22 +201,358 @@ impl Animation {
             cycles: 0,
             cycle_just_finished: 0,
             actual_depth: 0.0,
+
+            fade_from: NO_SLOT,
+            fade_elapsed: 0.0,
+            fade_duration: 0.0,
+
+            backwards: 0,
+        }
+    }
+
+    /// how far along the crossfade is, 1 when there is none
+    pub fn transition_progress(&self) -> f32 {
+        if self.fade_from == NO_SLOT || self.fade_duration <= 0.0 {
+            1.0
+        } else {
+            (self.fade_elapsed / self.fade_duration).clamp(0.0, 1.0)
+        }
+    }
+
+    /// advances the animation by one frame worth of `delta_time`, runs once per sprite per frame
+    fn step(&mut self, sprite: &Sprite, frame_durations: &[f32], delta_time: f32) {
+        if sprite.reversed != 0 && self.started == 0 {
+            self.current_frame = sprite.frames.saturating_sub(1);
+        }
+        if sprite.frames <= 1
+            || sprite.paused != 0
+            || (sprite.looping == 0 && self.loop_paused != 0)
+        {
+            return;
+        }
+        self.started = 1;
+        // sprites with held key frames look their duration up per frame
+        let duration = match sprite.durations_offset {
+            UNIFORM_DURATION => sprite.duration,
+            offset => frame_durations
+                .get((offset + self.current_frame) as usize)
+                .copied()
+                .unwrap_or(sprite.duration),
+        }
+        .max(0.0);
+        if self.counter < duration {
+            self.counter += delta_time * sprite.speed.max(0.0);
+            return;
+        }
+
+        let (first_frame, last_frame) = if sprite.reversed == 0 {
+            (0, sprite.frames - 1)
+        } else {
+            (sprite.frames - 1, 0)
+        };
+        // ping pong turns around at the last frame and the cycle ends back at the first one
+        let coming_back = sprite.ping_pong != 0 && self.backwards != 0;
+        let target = if coming_back { first_frame } else { last_frame };
+        if self.current_frame != target {
+            self.current_frame = if target > self.current_frame {
+                self.current_frame + 1
+            } else {
+                self.current_frame - 1
+            };
+            if self.current_frame == target && (sprite.ping_pong == 0 || coming_back) {
+                self.loop_paused = 1;
+            }
+            self.counter = 0.0;
+        } else if sprite.ping_pong != 0 && !coming_back {
+            self.backwards = 1;
+            self.current_frame = if first_frame > self.current_frame {
+                self.current_frame + 1
+            } else {
+                self.current_frame - 1
+            };
+            if self.current_frame == first_frame {
+                self.loop_paused = 1;
+            }
+            self.counter = 0.0;
+        } else if self.cycle_just_finished == 0 {
+            self.cycle_just_finished += 1;
+        } else if self.cycle_just_finished == 1 {
+            self.cycle_just_finished += 1;
+            self.cycles += 1;
+        } else {
+            self.current_frame = first_frame;
+            self.backwards = 0;
+            self.loop_paused = 0;
+            self.cycle_just_finished = 0;
+            self.counter = 0.0;
         }
     }
 }
 
-#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, Copy)]
-struct TextureDescription {
-    tex_x: u32,
-    tex_y: u32,
-    tex_width: u32,
-    tex_height: u32,
-    frames: u32,
-    origin: u32,
-    looping: u32,
-    frames_per_sec: u32,
+/// copies the atlas region and playback settings of a texture entry onto the sprite
+fn apply_texture(sprite: &mut Sprite, tex_data: &TextureDescription) {
+    sprite.tex_x = tex_data.tex_x as f32;
+    sprite.tex_y = tex_data.tex_y as f32;
+    sprite.tex_width = tex_data.tex_width as f32;
+    sprite.tex_height = tex_data.tex_height as f32;
+    sprite.width = tex_data.tex_width as f32;
+    sprite.height = tex_data.tex_height as f32;
+    sprite.frames = tex_data.frames;
+    sprite.looping = tex_data.looping;
+    sprite.duration = 1.0 / tex_data.frames_per_sec.max(1) as f32;
+    sprite.page = tex_data.page;
+    sprite.reversed = tex_data.reversed;
+    sprite.ping_pong = tex_data.ping_pong;
+    sprite.durations_offset = tex_data.durations_offset;
+    sprite.columns = tex_data.grid().0;
+}
+
+/// the depth the sprite ends up drawn at, has to stay in line with `vs_main`
+fn actual_depth(sprite: &Sprite, uniform: &Uniform) -> f32 {
+    let depth = sprite.base_depth;
+    if depth > 0.0 && depth < 0.5 {
+        depth * 0.6
+    } else if depth > 0.5 && depth < 1.0 {
+        (depth - 1.0) * 0.6 + 1.0
+    } else if depth == 0.5 {
+        let normalized_origin_y = ((uniform.global_offset_y + sprite.pos_y - sprite.origin)
+            / uniform.height_resolution)
+            .clamp(-2.0, 2.0);
+        0.1 * normalized_origin_y + 0.5
+    } else {
+        0.0
+    }
+}
+
+/// one entry of `texture.json`, the frames of an animation sit next to each other along x and wrap
+/// onto the next row after `columns` frames
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct TextureDescription {
+    pub tex_x: u32,
+    pub tex_y: u32,
+    /// size of a single frame
+    pub tex_width: u32,
+    pub tex_height: u32,
+    pub frames: u32,
+    pub origin: u32,
+    pub looping: u32,
+    pub frames_per_sec: u32,
+    /// index into the list of atlas pages, omitted means the first one
+    #[serde(default)]
+    pub page: u32,
+    #[serde(default)]
+    pub reversed: u32,
+    /// plays to the last frame and back again, the way back counts towards the same cycle
+    #[serde(default)]
+    pub ping_pong: u32,
+    /// seconds each frame is held for, one per frame, overrides `frames_per_sec` when present
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub frame_durations: Option<Vec<f32>>,
+    /// where `frame_durations` starts in the packed duration list
+    #[serde(skip)]
+    pub(crate) durations_offset: u32,
+    /// frames per row, omitted or 0 keeps every frame in a single row
+    #[serde(default)]
+    pub columns: u32,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub events: Vec<FrameEvent>,
+}
+
+/// a named event fired whenever `frame` becomes the current frame of the animation
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq)]
+pub struct FrameEvent {
+    pub frame: u32,
+    pub name: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct AnimationEvent {
+    /// sparse index of the sprite the animation belongs to
+    pub sparse_index: usize,
+    pub texture: TextureId,
+    pub name: String,
+    pub frame: u32,
+}
+
+/// read on state, every frame event that fired since the last tick
+#[derive(Debug, Default)]
+pub struct AnimationEvents {
+    events: Vec<AnimationEvent>,
+}
+impl AnimationEvents {
+    /// reset after each tick
+    fn reset(&mut self) {
+        self.events.clear();
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &AnimationEvent> {
+        self.events.iter()
+    }
+
+    /// events of a single sprite
+    pub fn of(&self, sparse_index: usize) -> impl Iterator<Item = &AnimationEvent> {
+        self.events
+            .iter()
+            .filter(move |x| x.sparse_index == sparse_index)
+    }
+
+    pub fn fired(&self, sparse_index: usize, name: &str) -> bool {
+        self.of(sparse_index).any(|x| x.name == name)
+    }
+}
+impl TextureDescription {
+    fn events_on(&self, frame: u32) -> impl Iterator<Item = &FrameEvent> {
+        self.events.iter().filter(move |x| x.frame == frame)
+    }
+
+    /// columns and rows the frames actually take up
+    pub fn grid(&self) -> (u32, u32) {
+        let frames = self.frames.max(1);
+        let columns = if self.columns == 0 {
+            frames
+        } else {
+            self.columns.min(frames)
+        };
+        (columns, (frames + columns - 1) / columns)
+    }
+}
+
+/// where one source image ended up in the texture array, images larger than the device allows are
+/// split into tiles and every tile gets a layer of its own
+#[derive(Debug, Clone, Copy)]
+struct PageLayout {
+    first_layer: u32,
+    tile_width: u32,
+    tile_height: u32,
+    tiles_x: u32,
+}
+
+/// lays the per frame durations of every entry out back to back and remembers where
+/// each entry starts, sorted by name so the layout doesn't change between runs
+fn pack_frame_durations(
+    texture_map: &mut std::collections::HashMap<String, TextureDescription>,
+) -> Result<Vec<f32>, RendererError> {
+    let mut names: Vec<_> = texture_map.keys().cloned().collect();
+    names.sort_unstable();
+    let mut frame_durations = vec![];
+    for name in names {
+        let tex_data = texture_map.get_mut(&name).unwrap();
+        tex_data.durations_offset = match &tex_data.frame_durations {
+            Some(durations) => {
+                if durations.len() != tex_data.frames as usize {
+                    return Err(RendererError::InvalidTextureDescription {
+                        name,
+                        reason: "frame durations don't match the frame count",
+                    });
+                }
+                let offset = frame_durations.len() as u32;
+                frame_durations.extend_from_slice(durations);
+                offset
+            }
+            None => UNIFORM_DURATION,
+        };
+    }
+    Ok(frame_durations)
+}
+
+/// decodes every page, splitting the oversized ones, layers are returned in texture array order
+fn load_atlas_pages(
+    paths: &[std::path::PathBuf],
+    max_dimension: u32,
+) -> Result<(Vec<image::RgbaImage>, Vec<PageLayout>), RendererError> {
+    let mut layers = vec![];
+    let mut layouts = vec![];
+    for path in paths {
+        let page = image::io::Reader::open(path)
+            .map_err(|err| RendererError::AssetIo {
+                path: path.clone(),
+                source: err,
+            })?
+            .decode()
+            .map_err(|err| RendererError::AssetDecode {
+                path: path.clone(),
+                source: Box::new(err),
+            })?
+            .into_rgba8();
+
+        let tile_width = page.width().min(max_dimension);
+        let tile_height = page.height().min(max_dimension);
+        let tiles_x = (page.width() + tile_width - 1) / tile_width;
+        let tiles_y = (page.height() + tile_height - 1) / tile_height;
+        layouts.push(PageLayout {
+            first_layer: layers.len() as u32,
+            tile_width,
+            tile_height,
+            tiles_x,
+        });
+        if tiles_x == 1 && tiles_y == 1 {
+            layers.push(page);
+            continue;
+        }
+        for tile_y in 0..tiles_y {
+            for tile_x in 0..tiles_x {
+                let x = tile_x * tile_width;
+                let y = tile_y * tile_height;
+                layers.push(
+                    image::imageops::crop_imm(
+                        &page,
+                        x,
+                        y,
+                        tile_width.min(page.width() - x),
+                        tile_height.min(page.height() - y),
+                    )
+                    .to_image(),
+                );
+            }
+        }
+    }
+    Ok((layers, layouts))
+}
+
+/// turns the page and coordinates of a description, which refer to the source image, into a layer
+/// and coordinates local to that layer, the whole strip must end up on a single tile
+fn remap_to_layer(
+    name: &str,
+    tex_data: &mut TextureDescription,
+    layouts: &[PageLayout],
+) -> Result<(), RendererError> {
+    let layout = layouts.get(tex_data.page as usize).ok_or_else(|| {
+        RendererError::InvalidTextureDescription {
+            name: name.to_string(),
+            reason: "page index out of range",
+        }
+    })?;
+    let tile_x = tex_data.tex_x / layout.tile_width;
+    let tile_y = tex_data.tex_y / layout.tile_height;
+    let local_x = tex_data.tex_x % layout.tile_width;
+    let local_y = tex_data.tex_y % layout.tile_height;
+    let (columns, rows) = tex_data.grid();
+    if local_x + tex_data.tex_width * columns > layout.tile_width
+        || local_y + tex_data.tex_height * rows > layout.tile_height
+    {
+        return Err(RendererError::InvalidTextureDescription {
+            name: name.to_string(),
+            reason: "strip crosses the edge of a page",
+        });
+    }
+    tex_data.page = layout.first_layer + tile_y * layout.tiles_x + tile_x;
+    tex_data.tex_x = local_x;
+    tex_data.tex_y = local_y;
+    Ok(())
 }
 
+/// marks a sprite without per frame durations, `Sprite::duration` is used for every frame instead
+const UNIFORM_DURATION: u32 = !0;
+
+/// a slot that hasn't shown any frame yet
+const NO_FRAME: u32 = !0;
+
+/// no animation slot at all
+const NO_SLOT: u32 = !0;
+
+/// fixed ticks run per drawn frame at most, the simulation falls behind rather than stall
+const MAX_TICKS_PER_FRAME: u32 = 8;
+
 /// specify the depth as 0.5 to enable y sorting
 #[repr(C)]
 #[derive(Debug)]
@@ -91,8 +586,36 @@ pub struct Sprite {
 
     pub flipped_x: u32,
     pub flipped_y: u32,
+
+    page: u32,
+    durations_offset: u32,
+    columns: u32,
+
+    // region of the animation being faded out
+    fade_tex_x: f32,
+    fade_tex_y: f32,
+    fade_tex_width: f32,
+    fade_tex_height: f32,
+    fade_page: u32,
+    fade_columns: u32,
+
+    pub ping_pong: u32,
+    /// scales how fast the animation plays, 1 is normal speed
+    pub speed: f32,
+
+    // position at the start of the last tick, drawn positions blend from here
+    prev_pos_x: f32,
+    prev_pos_y: f32,
+
+    anim_generation: u32,
 }
 impl Sprite {
+    /// bitwise copy that shares the animation slot, only for drawing and stepping, never insert
+    /// or remove it
+    fn shallow_copy(&self) -> Self {
+        unsafe { std::ptr::read(self) }
+    }
+
     fn new_empty() -> Self {
         Self {
             pos_x: 0.0,
@@ -120,137 +643,380 @@ impl Sprite {
 
             flipped_x: 0,
             flipped_y: 0,
+
+            page: 0,
+            durations_offset: UNIFORM_DURATION,
+            columns: 1,
+
+            fade_tex_x: 0.0,
+            fade_tex_y: 0.0,
+            fade_tex_width: 0.0,
+            fade_tex_height: 0.0,
+            fade_page: 0,
+            fade_columns: 1,
+
+            ping_pong: 0,
+            speed: 1.0,
+
+            prev_pos_x: 0.0,
+            prev_pos_y: 0.0,
+
+            anim_generation: 0,
         }
     }
+
+    /// moves without blending from the old position when a fixed update rate is set
+    pub fn teleport(&mut self, x: f32, y: f32) {
+        self.pos_x = x;
+        self.pos_y = y;
+        self.prev_pos_x = x;
+        self.prev_pos_y = y;
+    }
+}
+
+/// an entry of the texture descriptions, resolved from its name once by the sprite master
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct TextureId(u32);
+
+/// anything the sprite master takes as a texture, names are looked up and ids used as they are
+pub trait TextureKey {
+    fn resolve(self, sprite_master: &SpriteMaster3000) -> Result<TextureId, RendererError>;
+}
+impl TextureKey for TextureId {
+    fn resolve(self, sprite_master: &SpriteMaster3000) -> Result<TextureId, RendererError> {
+        Ok(self)
+    }
+}
+impl TextureKey for &str {
+    fn resolve(self, sprite_master: &SpriteMaster3000) -> Result<TextureId, RendererError> {
+        sprite_master.texture_id(self)
+    }
+}
+impl TextureKey for &String {
+    fn resolve(self, sprite_master: &SpriteMaster3000) -> Result<TextureId, RendererError> {
+        sprite_master.texture_id(self)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SlotStats {
+    pub used: u32,
+    pub free: u32,
+    pub capacity: u32,
+    /// the most slots that were ever in use at once
+    pub high_water: u32,
 }
 
 pub struct SpriteMaster3000<'this> {
-    map: std::collections::HashMap<String, TextureDescription>,
+    // texture descriptions indexed by `TextureId`
+    textures: Vec<TextureDescription>,
+    texture_names: Vec<String>,
+    texture_ids: std::collections::HashMap<String, TextureId>,
     occupied_indices: Vec<bool>,
-    names: Vec<&'static str>,
+    // outgoing slots of a crossfade, they keep playing but their events are left out
+    fading_out: Vec<bool>,
+    // freed slots, the most recently freed one is handed out first
+    free_slots: Vec<u32>,
+    // bumped every time a slot is freed so sprites still pointing at it can be told apart
+    generations: Vec<u32>,
+    high_water: u32,
+    // texture playing in each slot
+    names: Vec<Option<TextureId>>,
     anim_data: Vec<Animation>,
+    // sparse index of the sprite holding each slot, events are reported with it
+    owners: Vec<usize>,
+    // frame each slot was on when events were last collected
+    last_frames: Vec<u32>,
+    // cycle count each slot had when the controllers last looked, so a cycle ends only once
+    seen_cycles: Vec<u32>,
+    frame_durations: Vec<f32>,
+
+    // slots double up to this many when they run out
+    max_capacity: u32,
 
     table: &'this mut ecs::Table,
-    queue: &'this wgpu::Queue,
-    buffer: &'this wgpu::Buffer,
 }
 impl<'this> SpriteMaster3000<'this> {
+    /// needs no gpu, the renderer uploads the animation data itself
     fn new(
-        mut current_dir: std::path::PathBuf,
+        mut map: std::collections::HashMap<String, TextureDescription>,
         sprite_num: u32,
+        max_capacity: u32,
         table: *mut ecs::Table,
-        queue: *const wgpu::Queue,
-        buffer: *const wgpu::Buffer,
-        anim_data: Vec<Animation>,
-    ) -> Self {
-        current_dir.push("res/texture.json");
-        let val = std::fs::read(current_dir).expect("cannot open file from this directory, either file not exist or don't have the permission");
-        let map: std::collections::HashMap<String, TextureDescription> =
-            serde_json::from_slice(&val).expect("corrupt file format");
-        Self {
-            map,
+    ) -> Result<Self, RendererError> {
+        let frame_durations = pack_frame_durations(&mut map)?;
+        // sorted so the ids don't change between runs
+        let mut map: Vec<_> = map.into_iter().collect();
+        map.sort_unstable_by(|x, y| x.0.cmp(&y.0));
+        let texture_ids = map
+            .iter()
+            .enumerate()
+            .map(|(id, (name, _))| (name.clone(), TextureId(id as u32)))
+            .collect();
+        let (texture_names, textures) = map.into_iter().unzip();
+        Ok(Self {
+            textures,
+            texture_names,
+            texture_ids,
             occupied_indices: vec![false; sprite_num as usize],
+            fading_out: vec![false; sprite_num as usize],
+            free_slots: (0..sprite_num).rev().collect(),
+            generations: vec![0; sprite_num as usize],
+            high_water: 0,
+            max_capacity,
             table: unsafe { table.as_mut().unwrap() },
-            queue: unsafe { queue.as_ref().unwrap() },
-            buffer: unsafe { buffer.as_ref().unwrap() },
-            anim_data,
-            names: vec![""; sprite_num as usize],
+            anim_data: vec![Animation::new_empty(); sprite_num as usize],
+            names: vec![None; sprite_num as usize],
+            owners: vec![!0; sprite_num as usize],
+            last_frames: vec![NO_FRAME; sprite_num as usize],
+            seen_cycles: vec![0; sprite_num as usize],
+            frame_durations,
             // collisions: vec![0; (sprite_num as f32 / 32.0).ceil() as usize],
+        })
+    }
+
+    fn load_descriptions(
+        path: &std::path::Path,
+    ) -> Result<std::collections::HashMap<String, TextureDescription>, RendererError> {
+        let val = std::fs::read(path).map_err(|err| RendererError::AssetIo {
+            path: path.to_path_buf(),
+            source: err,
+        })?;
+        serde_json::from_slice(&val).map_err(|err| RendererError::AssetDecode {
+            path: path.to_path_buf(),
+            source: Box::new(err),
+        })
+    }
+
+    pub fn read_anim_data(&self, access: &Access<Sprite>) -> Result<Animation, RendererError> {
+        Ok(self.anim_data[self.slot(access)?])
+    }
+
+    pub fn get_name(&self, access: &Access<Sprite>) -> Result<&str, RendererError> {
+        Ok(self.texture_name(self.get_texture(access)?))
+    }
+
+    pub fn get_texture(&self, access: &Access<Sprite>) -> Result<TextureId, RendererError> {
+        self.names[self.slot(access)?].ok_or(RendererError::StaleSlot(access.anim_buffer_index))
+    }
+
+    /// resolves a name once so hot paths can skip the lookup
+    pub fn texture_id(&self, name: &str) -> Result<TextureId, RendererError> {
+        self.texture_ids
+            .get(name)
+            .copied()
+            .ok_or_else(|| RendererError::UnknownTexture(name.to_string()))
+    }
+
+    pub fn texture_name(&self, id: TextureId) -> &str {
+        &self.texture_names[id.0 as usize]
+    }
+
+    pub fn texture_description(&self, id: TextureId) -> &TextureDescription {
+        &self.textures[id.0 as usize]
+    }
+
+    /// jumps to `frame` on the way forward and starts holding it from the beginning
+    pub fn seek(&mut self, access: &Access<Sprite>, frame: u32) -> Result<(), RendererError> {
+        self.slot(access)?;
+        if frame >= access.frames {
+            return Err(RendererError::FrameOutOfRange {
+                frame,
+                frames: access.frames,
+            });
+        }
+        let position = if access.reversed != 0 {
+            access.frames - 1 - frame
+        } else {
+            frame
+        };
+        self.place(access, position);
+        Ok(())
+    }
+
+    /// plays the animation from its first frame again, the cycle count starts over too
+    pub fn restart(&mut self, access: &Access<Sprite>) -> Result<(), RendererError> {
+        let index = self.slot(access)?;
+        let anim = &mut self.anim_data[index];
+        anim.counter = 0.0;
+        anim.cycles = 0;
+        self.place(access, 0);
+        Ok(())
+    }
+
+    /// jumps to a point of the cycle, 0 being its start and 1 its end, the way back included for
+    /// ping pong animations
+    pub fn set_progress(
+        &mut self,
+        access: &Access<Sprite>,
+        progress: f32,
+    ) -> Result<(), RendererError> {
+        self.slot(access)?;
+        let frames = access.frames.max(1);
+        let cycle = if access.ping_pong != 0 && frames > 1 {
+            2 * frames - 2
+        } else {
+            frames
+        };
+        let position = ((progress.clamp(0.0, 1.0) * cycle as f32) as u32).min(cycle - 1);
+        self.place(access, position);
+        Ok(())
+    }
+
+    /// puts the animation at a step of its cycle, counted from the first frame in play order
+    fn place(&mut self, sprite: &Sprite, position: u32) {
+        let frames = sprite.frames.max(1);
+        let (step, backwards) = if position < frames {
+            (position, 0)
+        } else {
+            (2 * frames - 2 - position, 1)
+        };
+        let index = sprite.anim_buffer_index as usize;
+        let anim = &mut self.anim_data[index];
+        anim.current_frame = if sprite.reversed != 0 {
+            frames - 1 - step
+        } else {
+            step
+        };
+        anim.backwards = backwards;
+        anim.started = 1;
+        anim.counter = 0.0;
+        anim.cycle_just_finished = 0;
+        anim.loop_paused = (sprite.ping_pong == 0 && step == frames - 1) as u32;
+        // the frame that was jumped to fires its events again
+        self.last_frames[index] = NO_FRAME;
+    }
+
+    fn request_index(&mut self) -> Result<u32, RendererError> {
+        if self.free_slots.is_empty() {
+            self.grow()?;
+        }
+        let buffer_index = self.free_slots.pop().unwrap();
+        let each = buffer_index as usize;
+        self.occupied_indices[each] = true;
+        self.anim_data[each] = Animation::new_empty();
+        // so that the first frame of the new animation fires its events
+        self.last_frames[each] = NO_FRAME;
+        self.high_water = self
+            .high_water
+            .max(self.capacity() - self.free_slots.len() as u32);
+        Ok(buffer_index)
+    }
+
+    /// the slot the sprite points at, as long as it still belongs to it
+    fn slot(&self, sprite: &Sprite) -> Result<usize, RendererError> {
+        let index = sprite.anim_buffer_index as usize;
+        if self.occupied_indices.get(index) == Some(&true)
+            && self.generations[index] == sprite.anim_generation
+        {
+            Ok(index)
+        } else {
+            Err(RendererError::StaleSlot(sprite.anim_buffer_index))
         }
     }
 
-    pub fn read_anim_data(&self, access: &Access<Sprite>) -> Result<Animation, &'static str> {
-        Ok(self.anim_data[access.anim_buffer_index as usize])
+    fn assign_slot(&self, sprite: &mut Sprite, buffer_index: u32) {
+        sprite.anim_buffer_index = buffer_index;
+        sprite.anim_generation = self.generations[buffer_index as usize];
+    }
+
+    pub fn slot_stats(&self) -> SlotStats {
+        let free = self.free_slots.len() as u32;
+        SlotStats {
+            used: self.capacity() - free,
+            free,
+            capacity: self.capacity(),
+            high_water: self.high_water,
+        }
     }
 
-    pub fn get_name(&self, access: &Access<Sprite>) -> Result<&'static str, &'static str> {
-        Ok(self.names[access.anim_buffer_index as usize])
+    /// number of animation slots, the gpu buffers are resized to match before drawing
+    pub fn capacity(&self) -> u32 {
+        self.occupied_indices.len() as u32
     }
 
-    fn request_index(&mut self) -> u32 {
-        let mut buffer_index = None::<u32>;
-        for each in 0..self.occupied_indices.len() {
-            if self.occupied_indices[each] == false {
-                self.occupied_indices[each] = true;
-                buffer_index = Some(each as u32);
-                break;
-            }
+    fn grow(&mut self) -> Result<(), RendererError> {
+        let capacity = self.capacity();
+        let new_capacity = capacity.saturating_mul(2).min(self.max_capacity);
+        if new_capacity <= capacity {
+            return Err(RendererError::CapacityExhausted(capacity));
         }
-        buffer_index.expect("not enough space")
+        let new_capacity = new_capacity as usize;
+        self.occupied_indices.resize(new_capacity, false);
+        self.fading_out.resize(new_capacity, false);
+        self.anim_data.resize(new_capacity, Animation::new_empty());
+        self.names.resize(new_capacity, None);
+        self.owners.resize(new_capacity, !0);
+        self.last_frames.resize(new_capacity, NO_FRAME);
+        self.seen_cycles.resize(new_capacity, 0);
+        self.generations.resize(new_capacity, 0);
+        self.free_slots
+            .extend((capacity..new_capacity as u32).rev());
+        Ok(())
     }
 
     pub fn set_anim_data(
         &mut self,
-        texture: &'static str,
+        texture: impl TextureKey,
         sprite: &mut Sprite,
-    ) -> Result<(), &'static str> {
-        let tex_data = self.map.get(texture).ok_or("wrong texture name")?;
-        sprite.tex_x = tex_data.tex_x as f32;
-        sprite.tex_y = tex_data.tex_y as f32;
-        sprite.tex_width = tex_data.tex_width as f32;
-        sprite.tex_height = tex_data.tex_height as f32;
-        sprite.width = tex_data.tex_width as f32;
-        sprite.height = tex_data.tex_height as f32;
-        sprite.frames = tex_data.frames;
-        sprite.looping = tex_data.looping;
-        sprite.duration = 1.0 / tex_data.frames_per_sec.max(1) as f32;
+    ) -> Result<(), RendererError> {
+        let texture = texture.resolve(self)?;
+        apply_texture(sprite, self.texture_description(texture));
         Ok(())
     }
 
     pub fn add_sprite(
         &mut self,
-        texture: &'static str,
+        texture: impl TextureKey,
         pos: (f32, f32),
         depth: f32,
-    ) -> Result<ecs::Access<Sprite>, &'static str> {
-        let mut buffer_index = self.request_index();
+    ) -> Result<ecs::Access<Sprite>, RendererError> {
+        let texture = texture.resolve(self)?;
         let mut sprite = Sprite::new_empty();
-
-        sprite.anim_buffer_index = buffer_index;
-        self.names[buffer_index as usize] = texture;
-
         self.set_anim_data(texture, &mut sprite)?;
 
+        let mut buffer_index = self.request_index()?;
+        self.assign_slot(&mut sprite, buffer_index);
+        self.names[buffer_index as usize] = Some(texture);
+
         sprite.base_depth = depth;
-        sprite.pos_x = pos.0;
-        sprite.pos_y = pos.1;
+        sprite.teleport(pos.0, pos.1);
 
-        Ok(self.table.insert_new(sprite))
+        self.insert_new(sprite)
     }
 
     pub fn insert_sprite(
         &mut self,
         sparse_index: usize,
-        texture: &'static str,
+        texture: impl TextureKey,
         pos: (f32, f32),
         depth: f32,
-    ) -> Result<ecs::Access<Sprite>, &'static str> {
-        let mut buffer_index = self.request_index();
-
+    ) -> Result<ecs::Access<Sprite>, RendererError> {
+        let texture = texture.resolve(self)?;
         let mut sprite = Sprite::new_empty();
-        sprite.anim_buffer_index = buffer_index;
-        self.names[buffer_index as usize] = texture;
-
         self.set_anim_data(texture, &mut sprite)?;
 
+        let mut buffer_index = self.request_index()?;
+        self.assign_slot(&mut sprite, buffer_index);
+        self.names[buffer_index as usize] = Some(texture);
+
         sprite.base_depth = depth;
-        sprite.pos_x = pos.0;
-        sprite.pos_y = pos.1;
+        sprite.teleport(pos.0, pos.1);
 
-        Ok(self.table.insert_at(sparse_index, sprite)?)
+        self.insert_at_or_free(sparse_index, sprite)
     }
 
     fn copy_sprite(
         &mut self,
         access_to_clone: &Access<Sprite>,
         requested_index: u32,
-    ) -> Result<Sprite, &'static str> {
+    ) -> Result<Sprite, RendererError> {
         unsafe {
             let sprite_clone = &**access_to_clone;
             let mut uninit_sprite: MaybeUninit<Sprite> = MaybeUninit::uninit();
             std::ptr::copy(sprite_clone, uninit_sprite.as_mut_ptr(), 1);
             let mut sprite = uninit_sprite.assume_init();
-            sprite.anim_buffer_index = requested_index;
+            self.assign_slot(&mut sprite, requested_index);
             self.names[requested_index as usize] =
                 self.names[sprite_clone.anim_buffer_index as usize];
             Ok(sprite)
@@ -261,98 +1027,292 @@ impl<'this> SpriteMaster3000<'this> {
         &mut self,
         access_to_clone: &Access<Sprite>,
         index_to_insert: usize,
-    ) -> Result<ecs::Access<Sprite>, &'static str> {
-        let mut buffer_index = self.request_index();
+    ) -> Result<ecs::Access<Sprite>, RendererError> {
+        let mut buffer_index = self.request_index()?;
         let mut sprite = self.copy_sprite(access_to_clone, buffer_index)?;
-        Ok(self.table.insert_at(index_to_insert, sprite)?)
+        self.insert_at_or_free(index_to_insert, sprite)
+    }
+
+    /// hands the slot back if the table refuses the sparse index, so a failed insert doesn't leak it
+    fn insert_at_or_free(
+        &mut self,
+        sparse_index: usize,
+        sprite: Sprite,
+    ) -> Result<ecs::Access<Sprite>, RendererError> {
+        let buffer_index = sprite.anim_buffer_index;
+        match self.table.insert_at(sparse_index, sprite) {
+            Ok(access) => {
+                self.owners[buffer_index as usize] = sparse_index;
+                Ok(access)
+            }
+            Err(err) => {
+                self.free_index(buffer_index);
+                Err(err.into())
+            }
+        }
     }
 
     pub fn clone_add(
         &mut self,
         access_to_clone: &Access<Sprite>,
-    ) -> Result<ecs::Access<Sprite>, &'static str> {
-        let mut buffer_index = self.request_index();
+    ) -> Result<ecs::Access<Sprite>, RendererError> {
+        let mut buffer_index = self.request_index()?;
         let mut sprite = self.copy_sprite(access_to_clone, buffer_index)?;
-        Ok(self.table.insert_new(sprite))
+        self.insert_new(sprite)
     }
 
-    fn free_index(&mut self, anim_buffer_index: u32) {
-        let index_size = anim_buffer_index as u64 * std::mem::size_of::<Animation>() as u64;
-        self.queue.write_buffer(
-            self.buffer,
-            index_size,
-            bytemuck::cast_slice(&[Animation::new_empty()]),
-        );
+    fn insert_new(&mut self, sprite: Sprite) -> Result<ecs::Access<Sprite>, RendererError> {
+        let buffer_index = sprite.anim_buffer_index;
+        let access = self.table.insert_new(sprite);
+        self.owners[buffer_index as usize] = access.get_sparse_index()?;
+        Ok(access)
+    }
+
+    /// steps every animation exactly once and queues the frame events
+    fn step_animations(
+        &mut self,
+        sprites: &[Sprite],
+        uniform: &Uniform,
+    ) -> Result<(), RendererError> {
+        for sprite in sprites {
+            let index = sprite.anim_buffer_index as usize;
+            let anim = &mut self.anim_data[index];
+            anim.step(sprite, &self.frame_durations, uniform.delta_time);
+            anim.actual_depth = actual_depth(sprite, uniform);
+
+            let fade_from = anim.fade_from;
+            if fade_from == NO_SLOT {
+                continue;
+            }
+            anim.fade_elapsed += uniform.delta_time;
+            if anim.fade_elapsed >= anim.fade_duration {
+                anim.fade_from = NO_SLOT;
+                self.free_index(fade_from);
+                continue;
+            }
+            // the outgoing animation keeps advancing with its own texture data
+            let mut outgoing = sprite.shallow_copy();
+            if let Some(texture) = self.names[fade_from as usize] {
+                apply_texture(&mut outgoing, &self.textures[texture.0 as usize]);
+            }
+            self.anim_data[fade_from as usize].step(
+                &outgoing,
+                &self.frame_durations,
+                uniform.delta_time,
+            );
+        }
+        self.collect_events()
+    }
+
+    /// compares the freshly stepped animation data with what was seen last time and queues the
+    /// events of every frame entered since, skipped frames included
+    fn collect_events(&mut self) -> Result<(), RendererError> {
+        let mut events = vec![];
+        for index in 0..self.occupied_indices.len() {
+            if !self.occupied_indices[index] || self.fading_out[index] {
+                continue;
+            }
+            let current_frame = self.anim_data[index].current_frame;
+            let last_frame = self.last_frames[index];
+            if current_frame == last_frame {
+                continue;
+            }
+            self.last_frames[index] = current_frame;
+            let Some(texture) = self.names[index] else {
+                continue;
+            };
+            let tex_data = &self.textures[texture.0 as usize];
+            if tex_data.events.is_empty() {
+                continue;
+            }
+
+            let frames = tex_data.frames.max(1);
+            let playing_down = (tex_data.reversed != 0) != (self.anim_data[index].backwards != 0);
+            // walk back towards the last seen frame against the direction the animation plays,
+            // ping pong animations don't wrap around so the walk stops at the ends
+            let mut first = current_frame;
+            let mut entered = 1;
+            while last_frame != NO_FRAME && entered < frames {
+                let at_end = if playing_down {
+                    first + 1 == frames
+                } else {
+                    first == 0
+                };
+                if tex_data.ping_pong != 0 && at_end {
+                    break;
+                }
+                let previous = if playing_down {
+                    (first + 1) % frames
+                } else {
+                    (first + frames - 1) % frames
+                };
+                if previous == last_frame {
+                    break;
+                }
+                first = previous;
+                entered += 1;
+            }
+            // then forward again so the events come in the order the frames played
+            let mut frame = first;
+            for _ in 0..entered {
+                for event in tex_data.events_on(frame) {
+                    events.push(AnimationEvent {
+                        sparse_index: self.owners[index],
+                        texture,
+                        name: event.name.clone(),
+                        frame,
+                    });
+                }
+                frame = if playing_down {
+                    (frame + frames - 1) % frames
+                } else {
+                    (frame + 1) % frames
+                };
+            }
+        }
+        self.table
+            .read_state::<AnimationEvents>()?
+            .events
+            .extend(events);
+        Ok(())
+    }
 
+    fn free_index(&mut self, anim_buffer_index: u32) {
+        if !self.occupied_indices[anim_buffer_index as usize] {
+            return;
+        }
+        let fading = self.anim_data[anim_buffer_index as usize].fade_from;
+        if fading != NO_SLOT {
+            self.free_index(fading);
+        }
+        self.anim_data[anim_buffer_index as usize] = Animation::new_empty();
         self.occupied_indices[anim_buffer_index as usize] = false;
-        self.names[anim_buffer_index as usize] = "";
+        self.fading_out[anim_buffer_index as usize] = false;
+        self.names[anim_buffer_index as usize] = None;
+        self.owners[anim_buffer_index as usize] = !0;
+        let generation = &mut self.generations[anim_buffer_index as usize];
+        *generation = generation.wrapping_add(1);
+        self.free_slots.push(anim_buffer_index);
     }
 
     pub fn remove_sprite(
         &mut self,
         sprite_access: ecs::Access<Sprite>,
-    ) -> Result<(), &'static str> {
+    ) -> Result<(), RendererError> {
+        let sparse_index = sprite_access.get_sparse_index()?;
         let sprite = self.table.remove::<Sprite>(sprite_access)?;
-        self.free_index(sprite.anim_buffer_index);
+        if let Ok(index) = self.slot(&sprite) {
+            self.free_index(index as u32);
+        }
+        self.table
+            .read_state::<AnimationControllers>()?
+            .detach(sparse_index);
+        Ok(())
+    }
+
+    /// lets every sprite with an animation controller take the transition its parameters ask for
+    fn run_controllers(&mut self) -> Result<(), RendererError> {
+        // both live in the table but never alias each other or the sprite master
+        let controllers = unsafe {
+            &mut *(self.table.read_state::<AnimationControllers>()? as *mut AnimationControllers)
+        };
+        let sprites = unsafe { &mut *(self.table.read_column::<Sprite>()? as *mut [Sprite]) };
+        for sprite in sprites {
+            let index = sprite.anim_buffer_index as usize;
+            let sparse_index = self.owners[index];
+            let anim = &self.anim_data[index];
+            // counts drop back to 0 on a restart or a fresh slot, only a rise is a finished cycle
+            let cycle_ended = anim.cycles > self.seen_cycles[index];
+            self.seen_cycles[index] = anim.cycles;
+            let cycle_finished = cycle_ended || (sprite.looping == 0 && anim.loop_paused != 0);
+            if let Some((texture, duration)) = controllers.evaluate(sparse_index, cycle_finished) {
+                self.switch_texture(sprite, sparse_index, texture, duration)?;
+            }
+        }
         Ok(())
     }
 
     pub fn change_state(
         &mut self,
         access: &mut Access<Sprite>,
-        texture: &'static str,
-    ) -> Result<(), &'static str> {
-        let mut buffer_index = self.request_index();
-        let tex_data = *self.map.get(texture).ok_or("wrong texture name")?;
-        let sprite = &mut *access;
+        texture: impl TextureKey,
+    ) -> Result<(), RendererError> {
+        self.change_state_with_transition(access, texture, 0.0)
+    }
+
+    /// same as `change_state` but the old animation keeps playing and fades out over `duration`
+    /// seconds while the new one fades in, a duration of 0 switches instantly
+    pub fn change_state_with_transition(
+        &mut self,
+        access: &mut Access<Sprite>,
+        texture: impl TextureKey,
+        duration: f32,
+    ) -> Result<(), RendererError> {
+        let texture = texture.resolve(self)?;
+        let sparse_index = access.get_sparse_index()?;
+        self.switch_texture(&mut *access, sparse_index, texture, duration)
+    }
+
+    /// an instant switch restarts the sprite's own slot, only a crossfade takes a second one
+    /// for the new animation while the old slot fades out and is freed once it's done
+    fn switch_texture(
+        &mut self,
+        sprite: &mut Sprite,
+        sparse_index: usize,
+        texture: TextureId,
+        duration: f32,
+    ) -> Result<(), RendererError> {
+        let tex_data = self.textures[texture.0 as usize].clone();
+        let old_index = self.slot(sprite)? as u32;
 
         if tex_data.tex_x as f32 == sprite.tex_x
             && tex_data.tex_y as f32 == sprite.tex_y
+            && tex_data.page == sprite.page
             && tex_data.frames == sprite.frames
             && tex_data.tex_height as f32 == sprite.tex_height
             && tex_data.tex_width as f32 == sprite.tex_width
+            && tex_data.durations_offset == sprite.durations_offset
+            && tex_data.grid().0 == sprite.columns
+            && tex_data.ping_pong == sprite.ping_pong
         {
             return Ok(());
-        } else {
-            // self.set_anim_data(texture, sprite)?;
-            {
-                sprite.tex_x = tex_data.tex_x as f32;
-                sprite.tex_y = tex_data.tex_y as f32;
-                sprite.tex_width = tex_data.tex_width as f32;
-                sprite.tex_height = tex_data.tex_height as f32;
-                sprite.width = tex_data.tex_width as f32;
-                sprite.height = tex_data.tex_height as f32;
-                sprite.frames = tex_data.frames;
-                sprite.looping = tex_data.looping;
-                sprite.duration = 1.0 / tex_data.frames_per_sec.max(1) as f32;
-            }
-
-            // self.free_index(sprite.anim_buffer_index);
+        }
 
-            {
-                let index_size =
-                    sprite.anim_buffer_index as u64 * std::mem::size_of::<Animation>() as u64;
-                self.queue.write_buffer(
-                    self.buffer,
-                    index_size,
-                    bytemuck::cast_slice(&[Animation::new_empty()]),
-                );
+        // a transition that is still running gets cut short, only the latest state fades out
+        let fading = std::mem::replace(&mut self.anim_data[old_index as usize].fade_from, NO_SLOT);
+        if fading != NO_SLOT {
+            self.free_index(fading);
+        }
 
-                self.occupied_indices[sprite.anim_buffer_index as usize] = false;
-                self.names[sprite.anim_buffer_index as usize] = "";
-            }
-
-            self.names[sprite.anim_buffer_index as usize] = "";
-            self.names[buffer_index as usize] = texture;
-
-            sprite.anim_buffer_index = buffer_index;
-            Ok(())
+        if duration > 0.0 {
+            let buffer_index = self.request_index()?;
+            sprite.fade_tex_x = sprite.tex_x;
+            sprite.fade_tex_y = sprite.tex_y;
+            sprite.fade_tex_width = sprite.tex_width;
+            sprite.fade_tex_height = sprite.tex_height;
+            sprite.fade_page = sprite.page;
+            sprite.fade_columns = sprite.columns;
+
+            let anim = &mut self.anim_data[buffer_index as usize];
+            anim.fade_from = old_index;
+            self.fading_out[old_index as usize] = true;
+            anim.fade_duration = duration;
+
+            self.names[buffer_index as usize] = Some(texture);
+            self.owners[buffer_index as usize] = sparse_index;
+            self.assign_slot(sprite, buffer_index);
+        } else {
+            self.anim_data[old_index as usize] = Animation::new_empty();
+            self.last_frames[old_index as usize] = NO_FRAME;
+            self.names[old_index as usize] = Some(texture);
         }
+        apply_texture(sprite, &tex_data);
+        Ok(())
     }
 }
 
-// todo add more collision shapes
-#[derive(Debug)]
+/// `pos` is the top left corner of the box, the shape sits centered on it
+#[derive(Debug, Clone, Copy)]
 pub struct CollisionRect {
     sparse_index: usize,
 
@@ -363,7 +1323,8 @@ pub struct CollisionRect {
     pub height: f32,
     pub actual_depth: f32,
     pub fuzzy_range: f32,
-    pub channel: u32,
+    pub filter: CollisionFilter,
+    pub shape: CollisionShape,
 }
 impl CollisionRect {
     fn new_empty() -> Self {
@@ -375,7 +1336,8 @@ impl CollisionRect {
             height: 0.0,
             actual_depth: 0.0,
             fuzzy_range: 0.0,
-            channel: 0,
+            filter: CollisionFilter::ALL,
+            shape: CollisionShape::Rect,
         }
     }
 
@@ -385,6 +1347,27 @@ impl CollisionRect {
         self.width = sprite.width;
         self.height = sprite.height;
     }
+
+    fn placed(&self) -> collision::Placed {
+        place_shape(
+            &self.shape,
+            (self.pos_x, self.pos_y),
+            (self.width, self.height),
+        )
+    }
+}
+
+/// `pos` is the top left corner of the box
+fn place_shape(shape: &CollisionShape, pos: (f32, f32), size: (f32, f32)) -> collision::Placed {
+    let half_size = [size.0 / 2.0, size.1 / 2.0];
+    let center = [pos.0 + half_size[0], pos.1 - half_size[1]];
+    collision::Placed::new(shape, center, half_size)
+}
+
+/// whether one depth lies within the fuzzy range above the other, as (depth, fuzzy range)
+fn depth_check(first: (f32, f32), second: (f32, f32)) -> bool {
+    (first.0 <= second.0 && first.0 + first.1 >= second.0)
+        || (second.0 <= first.0 && second.0 + second.1 >= first.0)
 }
 
 pub struct CollisionManager<'this> {
@@ -392,18 +1375,44 @@ pub struct CollisionManager<'this> {
 
     pub x_sorted_list: Vec<CollisionRect>,
 
-    pub x_collided: Vec<(CollisionRect, CollisionRect)>,
+    // shapes of `x_sorted_list` placed in the world, same order
+    placed_list: Vec<collision::Placed>,
+    // rects with their shapes while sorting, kept around for the allocation
+    sort_buffer: Vec<(collision::Placed, CollisionRect)>,
+    // pairs from the sweep as indices into `x_sorted_list`
+    x_pairs: Vec<(usize, usize)>,
+    // widest box along x, nothing that starts further left than this from a query reaches it
+    widest: f32,
 
-    // sorted with id
+    // sorted with id, the lower one first
     pub colliding_list: Vec<(usize, usize)>,
+    // same order as `colliding_list`
+    pub manifolds: Vec<Manifold>,
+
+    /// how the filters of a pair combine
+    pub filter_mode: FilterMode,
+    // pairs that never collide whatever their filters, sorted, the lower id first
+    ignored_pairs: Vec<(usize, usize)>,
+
+    // `colliding_list` of the tick before, diffed against the new one
+    previous_list: Vec<(usize, usize)>,
+    events: Vec<CollisionEvent>,
 }
 impl<'this> CollisionManager<'this> {
     fn new(table: *mut ecs::Table) -> Self {
         Self {
             table: unsafe { table.as_mut().unwrap() },
             x_sorted_list: vec![],
-            x_collided: vec![],
+            placed_list: vec![],
+            sort_buffer: vec![],
+            x_pairs: vec![],
+            widest: 0.0,
             colliding_list: vec![],
+            manifolds: vec![],
+            filter_mode: FilterMode::default(),
+            ignored_pairs: vec![],
+            previous_list: vec![],
+            events: vec![],
         }
     }
 
@@ -417,14 +1426,253 @@ impl<'this> CollisionManager<'this> {
         }
     }
 
+    /// the contact between two rects seen from `id_1`, `None` if they aren't colliding
+    pub fn manifold(&self, id_1: usize, id_2: usize) -> Option<Manifold> {
+        if let Ok(index) = self.colliding_list.binary_search(&(id_1, id_2)) {
+            Some(self.manifolds[index])
+        } else if let Ok(index) = self.colliding_list.binary_search(&(id_2, id_1)) {
+            Some(self.manifolds[index].flipped())
+        } else {
+            None
+        }
+    }
+
+    /// every contact of one rect, each seen from it so `first` is always `id`
+    pub fn manifolds_of(&self, id: usize) -> impl Iterator<Item = Manifold> + '_ {
+        self.manifolds.iter().filter_map(move |manifold| {
+            if manifold.first == id {
+                Some(*manifold)
+            } else if manifold.second == id {
+                Some(manifold.flipped())
+            } else {
+                None
+            }
+        })
+    }
+
+    /// how pairs started, kept or stopped touching on the last tick
+    pub fn events(&self) -> impl Iterator<Item = &CollisionEvent> {
+        self.events.iter()
+    }
+
+    /// events of a single rect, each seen from it so `first` is always `id`
+    pub fn events_of(&self, id: usize) -> impl Iterator<Item = CollisionEvent> + '_ {
+        self.events.iter().filter_map(move |event| {
+            if event.first == id {
+                Some(*event)
+            } else if event.second == id {
+                Some(event.flipped())
+            } else {
+                None
+            }
+        })
+    }
+
+    /// the two rects stop colliding with each other, but still with everything else
+    pub fn ignore_pair(&mut self, id_1: usize, id_2: usize) {
+        let pair = (id_1.min(id_2), id_1.max(id_2));
+        if let Err(index) = self.ignored_pairs.binary_search(&pair) {
+            self.ignored_pairs.insert(index, pair);
+        }
+    }
+
+    pub fn unignore_pair(&mut self, id_1: usize, id_2: usize) {
+        let pair = (id_1.min(id_2), id_1.max(id_2));
+        if let Ok(index) = self.ignored_pairs.binary_search(&pair) {
+            self.ignored_pairs.remove(index);
+        }
+    }
+
+    /// drops every ignored pair with the rect in it, for when its sparse index gets reused
+    pub fn unignore_all(&mut self, id: usize) {
+        self.ignored_pairs
+            .retain(|(first, second)| *first != id && *second != id);
+    }
+
+    pub fn is_ignored(&self, id_1: usize, id_2: usize) -> bool {
+        self.ignored_pairs
+            .binary_search(&(id_1.min(id_2), id_1.max(id_2)))
+            .is_ok()
+    }
+
+    fn filter_check(&self, first: &CollisionRect, second: &CollisionRect) -> bool {
+        first.filter.accepts(&second.filter, self.filter_mode)
+            && !self.is_ignored(first.sparse_index, second.sparse_index)
+    }
+
+    fn query_check(&self, rect: &CollisionRect, filter: &QueryFilter) -> bool {
+        if filter.mask & rect.filter.layer == 0 {
+            return false;
+        }
+        if let Some(id) = filter.exclude {
+            if id == rect.sparse_index || self.is_ignored(id, rect.sparse_index) {
+                return false;
+            }
+        }
+        match filter.depth {
+            Some(depth) => depth_check(depth, (rect.actual_depth, rect.fuzzy_range)),
+            None => true,
+        }
+    }
+
+    /// indices into `x_sorted_list` of the rects whose boxes overlap the given one and that pass
+    /// the filter, the list is sorted by the left edge so only the rects starting between
+    /// `min` minus the widest box and `max` are walked
+    fn candidates<'a>(
+        &'a self,
+        min: [f32; 2],
+        max: [f32; 2],
+        filter: &'a QueryFilter,
+    ) -> impl Iterator<Item = usize> + 'a {
+        let start = self
+            .placed_list
+            .partition_point(|placed| placed.aabb().0[0] < min[0] - self.widest);
+        let end = self
+            .placed_list
+            .partition_point(|placed| placed.aabb().0[0] <= max[0]);
+        (start..end.max(start))
+            .map(|index| (index, self.placed_list[index].aabb()))
+            .filter(move |(index, (rect_min, rect_max))| {
+                rect_max[0] >= min[0]
+                    && rect_max[1] >= min[1]
+                    && rect_min[1] <= max[1]
+                    && self.query_check(&self.x_sorted_list[*index], filter)
+            })
+            .map(|(index, _)| index)
+    }
+
+    /// sparse indices of the rects under a point, mouse picking and such
+    ///
+    /// like `colliding_list`, queries see the rects as they were on the last tick
+    pub fn query_point(&self, x: f32, y: f32, filter: QueryFilter) -> Vec<usize> {
+        self.candidates([x, y], [x, y], &filter)
+            .filter(|index| self.placed_list[*index].contains_point([x, y]))
+            .map(|index| self.x_sorted_list[index].sparse_index)
+            .collect()
+    }
+
+    /// sparse indices of the rects whose shapes overlap the box from `min` to `max`
+    pub fn query_aabb(&self, min: (f32, f32), max: (f32, f32), filter: QueryFilter) -> Vec<usize> {
+        let area = place_shape(
+            &CollisionShape::Rect,
+            (min.0, max.1),
+            (max.0 - min.0, max.1 - min.1),
+        );
+        self.candidates([min.0, min.1], [max.0, max.1], &filter)
+            .filter(|index| self.placed_list[*index].overlaps(&area))
+            .map(|index| self.x_sorted_list[index].sparse_index)
+            .collect()
+    }
+
+    /// the closest shape a ray hits within `max_distance`, `direction` doesn't need unit length
+    pub fn raycast(
+        &self,
+        origin: (f32, f32),
+        direction: (f32, f32),
+        max_distance: f32,
+        filter: QueryFilter,
+    ) -> Option<RayHit> {
+        let length = (direction.0 * direction.0 + direction.1 * direction.1).sqrt();
+        if length == 0.0 {
+            return None;
+        }
+        let direction = [direction.0 / length, direction.1 / length];
+        let origin = [origin.0, origin.1];
+        let end = [
+            origin[0] + direction[0] * max_distance,
+            origin[1] + direction[1] * max_distance,
+        ];
+        let min = [origin[0].min(end[0]), origin[1].min(end[1])];
+        let max = [origin[0].max(end[0]), origin[1].max(end[1])];
+
+        let mut closest: Option<RayHit> = None;
+        for index in self.candidates(min, max, &filter) {
+            let reach = closest.map_or(max_distance, |hit| hit.distance);
+            let Some((distance, normal)) =
+                self.placed_list[index].raycast(origin, direction, reach)
+            else {
+                continue;
+            };
+            closest = Some(RayHit {
+                sparse_index: self.x_sorted_list[index].sparse_index,
+                distance,
+                point: (
+                    origin[0] + direction[0] * distance,
+                    origin[1] + direction[1] * distance,
+                ),
+                normal: (normal[0], normal[1]),
+            });
+        }
+        closest
+    }
+
+    /// moves a shape, boxed by `pos` and `size` like a rect, along `direction` and returns the
+    /// first rect it would run into within `max_distance`
+    pub fn shape_cast(
+        &self,
+        shape: CollisionShape,
+        pos: (f32, f32),
+        size: (f32, f32),
+        direction: (f32, f32),
+        max_distance: f32,
+        filter: QueryFilter,
+    ) -> Option<ShapeHit> {
+        let length = (direction.0 * direction.0 + direction.1 * direction.1).sqrt();
+        if length == 0.0 {
+            return None;
+        }
+        let direction = [direction.0 / length, direction.1 / length];
+        let moving = place_shape(&shape, pos, size);
+
+        // the box covering the whole sweep
+        let (start_min, start_max) = moving.aabb();
+        let offset = [direction[0] * max_distance, direction[1] * max_distance];
+        let min = [
+            start_min[0] + offset[0].min(0.0),
+            start_min[1] + offset[1].min(0.0),
+        ];
+        let max = [
+            start_max[0] + offset[0].max(0.0),
+            start_max[1] + offset[1].max(0.0),
+        ];
+
+        let mut closest: Option<ShapeHit> = None;
+        for index in self.candidates(min, max, &filter) {
+            let reach = closest.map_or(max_distance, |hit| hit.distance);
+            let Some((distance, contact)) = moving.cast(direction, reach, &self.placed_list[index])
+            else {
+                continue;
+            };
+            closest = Some(ShapeHit {
+                sparse_index: self.x_sorted_list[index].sparse_index,
+                distance,
+                contact,
+            });
+        }
+        closest
+    }
+
     pub fn add_collision_rect(
         &mut self,
         pos: (f32, f32),
         size: (f32, f32),
         depth: f32,
         fuzzy_range: f32,
-        channel: u32,
-    ) -> ecs::Access<CollisionRect> {
+        filter: CollisionFilter,
+    ) -> Result<ecs::Access<CollisionRect>, RendererError> {
+        self.add_collision_shape(pos, size, CollisionShape::Rect, depth, fuzzy_range, filter)
+    }
+
+    /// same as `add_collision_rect` with the shape centered on the box given by `pos` and `size`
+    pub fn add_collision_shape(
+        &mut self,
+        pos: (f32, f32),
+        size: (f32, f32),
+        shape: CollisionShape,
+        depth: f32,
+        fuzzy_range: f32,
+        filter: CollisionFilter,
+    ) -> Result<ecs::Access<CollisionRect>, RendererError> {
         let collision_rect = CollisionRect {
             sparse_index: 0,
             pos_x: pos.0,
@@ -433,13 +1681,14 @@ impl<'this> CollisionManager<'this> {
             height: size.1,
             actual_depth: depth,
             fuzzy_range,
-            channel,
+            filter,
+            shape,
         };
         // let (index, shape) = self.table.insert_new(collision_rect);
         // shape.sparse_index = index;
         let mut rect = self.table.insert_new(collision_rect);
-        (*rect).sparse_index = rect.get_sparse_index().unwrap();
-        rect
+        (*rect).sparse_index = rect.get_sparse_index()?;
+        Ok(rect)
     }
 
     pub fn insert_collision_rect(
@@ -449,8 +1698,30 @@ impl<'this> CollisionManager<'this> {
         size: (f32, f32),
         depth: f32,
         fuzzy_range: f32,
-        channel: u32,
-    ) -> Result<ecs::Access<CollisionRect>, &'static str> {
+        filter: CollisionFilter,
+    ) -> Result<ecs::Access<CollisionRect>, RendererError> {
+        self.insert_collision_shape(
+            sparse_index,
+            pos,
+            size,
+            CollisionShape::Rect,
+            depth,
+            fuzzy_range,
+            filter,
+        )
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn insert_collision_shape(
+        &mut self,
+        sparse_index: usize,
+        pos: (f32, f32),
+        size: (f32, f32),
+        shape: CollisionShape,
+        depth: f32,
+        fuzzy_range: f32,
+        filter: CollisionFilter,
+    ) -> Result<ecs::Access<CollisionRect>, RendererError> {
         let collision_rect = CollisionRect {
             sparse_index,
             pos_x: pos.0,
@@ -459,79 +1730,143 @@ impl<'this> CollisionManager<'this> {
             height: size.1,
             actual_depth: depth,
             fuzzy_range,
-            channel,
+            filter,
+            shape,
         };
 
         Ok(self.table.insert_at(sparse_index, collision_rect)?)
     }
 
     /// basically if you update the position data of some rect, the collision result will be available at the next frame
-    fn update(&mut self) {
-        unsafe {
-            self.x_collided.clear();
-            self.colliding_list.clear();
-
-            let list_of_rects = self.table.read_column::<CollisionRect>().unwrap();
-
-            if self.x_sorted_list.capacity() < list_of_rects.len() {
-                self.x_sorted_list.reserve(list_of_rects.len());
-                self.x_sorted_list.set_len(list_of_rects.len());
+    ///
+    /// the broad phase sweeps the boxes around the shapes along x, the pairs left over get the
+    /// exact shape test
+    fn update(&mut self) -> Result<(), RendererError> {
+        std::mem::swap(&mut self.previous_list, &mut self.colliding_list);
+        self.colliding_list.clear();
+        self.manifolds.clear();
+        self.events.clear();
+
+        self.x_pairs.clear();
+
+        // every shape is placed once, sorting only reads the boxes they keep
+        let list_of_rects = unsafe { self.table.read_column::<CollisionRect>()? };
+        self.sort_buffer.clear();
+        self.sort_buffer
+            .extend(list_of_rects.iter().map(|rect| (rect.placed(), *rect)));
+        self.sort_buffer
+            .par_sort_unstable_by(|x, y| x.0.aabb().0[0].total_cmp(&y.0.aabb().0[0]));
+        self.placed_list.clear();
+        self.placed_list
+            .extend(self.sort_buffer.iter().map(|(placed, _)| *placed));
+        self.x_sorted_list.clear();
+        self.x_sorted_list
+            .extend(self.sort_buffer.iter().map(|(_, rect)| *rect));
+        self.widest = self
+            .placed_list
+            .iter()
+            .map(|placed| placed.aabb().1[0] - placed.aabb().0[0])
+            .fold(0.0, f32::max);
+
+        for current_index in 0..self.x_sorted_list.len() {
+            let (_, current_max) = self.placed_list[current_index].aabb();
+            for check_against_index in current_index + 1..self.x_sorted_list.len() {
+                let (check_against_min, _) = self.placed_list[check_against_index].aabb();
+                if current_max[0] >= check_against_min[0] {
+                    self.x_pairs.push((current_index, check_against_index));
+                } else {
+                    // breaking the inner loop
+                    break;
+                }
             }
-
-            std::ptr::copy(
-                list_of_rects.as_ptr(),
-                self.x_sorted_list.as_mut_ptr(),
-                list_of_rects.len(),
+        }
+        for &(first_index, second_index) in &self.x_pairs {
+            let (first_shape, second_shape) = (
+                &self.placed_list[first_index],
+                &self.placed_list[second_index],
             );
-            self.x_sorted_list[0..list_of_rects.len()]
-                .par_sort_unstable_by(|x, y| x.pos_x.total_cmp(&y.pos_x));
-
-            for current_index in 0..list_of_rects.len() {
-                for check_against in 1..list_of_rects.len() - current_index {
-                    let check_against_index = check_against + current_index;
-                    let mut current_rect_uninit: MaybeUninit<CollisionRect> = MaybeUninit::uninit();
-                    let mut check_against_rect_uninit: MaybeUninit<CollisionRect> =
-                        MaybeUninit::uninit();
-                    std::ptr::copy(
-                        &self.x_sorted_list[current_index],
-                        current_rect_uninit.as_mut_ptr(),
-                        1,
-                    );
-                    std::ptr::copy(
-                        &self.x_sorted_list[check_against_index],
-                        check_against_rect_uninit.as_mut_ptr(),
-                        1,
-                    );
-                    let current_rect = current_rect_uninit.assume_init();
-                    let check_against_rect = check_against_rect_uninit.assume_init();
-                    if current_rect.pos_x + current_rect.width >= check_against_rect.pos_x {
-                        self.x_collided.push((current_rect, check_against_rect));
-                    } else {
-                        // breaking the inner loop
-                        break;
-                    }
-                }
+            let (first_min, first_max) = first_shape.aabb();
+            let (second_min, second_max) = second_shape.aabb();
+            if first_max[1] < second_min[1] || second_max[1] < first_min[1] {
+                continue;
             }
-            for (first_rect, second_rect) in &self.x_collided {
-                let y_check = (first_rect.pos_y - first_rect.height <= second_rect.pos_y
-                    && first_rect.pos_y >= second_rect.pos_y)
-                    || (second_rect.pos_y - second_rect.height <= first_rect.pos_y
-                        && second_rect.pos_y >= first_rect.pos_y);
-                let channel_check = first_rect.channel == second_rect.channel;
-                let depth_check = (first_rect.actual_depth <= second_rect.actual_depth
-                    && first_rect.actual_depth + first_rect.fuzzy_range
-                        >= second_rect.actual_depth)
-                    || (second_rect.actual_depth <= first_rect.actual_depth
-                        && second_rect.actual_depth + second_rect.fuzzy_range
-                            >= first_rect.actual_depth);
-                if y_check && channel_check && depth_check {
-                    self.colliding_list
-                        .push((first_rect.sparse_index, second_rect.sparse_index));
-                }
+            let (first_rect, second_rect) = (
+                &self.x_sorted_list[first_index],
+                &self.x_sorted_list[second_index],
+            );
+            let filter_check = self.filter_check(first_rect, second_rect);
+            let depth_check = depth_check(
+                (first_rect.actual_depth, first_rect.fuzzy_range),
+                (second_rect.actual_depth, second_rect.fuzzy_range),
+            );
+            if !(filter_check && depth_check) {
+                continue;
+            }
+            if let Some(contact) = first_shape.contact(second_shape) {
+                let manifold = Manifold {
+                    first: first_rect.sparse_index,
+                    second: second_rect.sparse_index,
+                    contact,
+                };
+                // the sweep order changes as rects move, pairs keep theirs across ticks
+                self.manifolds.push(if manifold.first > manifold.second {
+                    manifold.flipped()
+                } else {
+                    manifold
+                });
             }
+        }
+
+        // so that binary search would actually work
+        self.manifolds
+            .par_sort_unstable_by_key(|manifold| (manifold.first, manifold.second));
+        self.colliding_list.extend(
+            self.manifolds
+                .iter()
+                .map(|manifold| (manifold.first, manifold.second)),
+        );
+
+        // both lists are sorted, walking them side by side finds what changed
+        let (mut new, mut old) = (0, 0);
+        while new < self.colliding_list.len() || old < self.previous_list.len() {
+            let ordering = match (self.colliding_list.get(new), self.previous_list.get(old)) {
+                (Some(new_pair), Some(old_pair)) => new_pair.cmp(old_pair),
+                (Some(_), None) => Ordering::Less,
+                _ => Ordering::Greater,
+            };
+            let event = match ordering {
+                Ordering::Less => {
+                    new += 1;
+                    self.manifold_event(CollisionPhase::Enter, new - 1)
+                }
+                Ordering::Equal => {
+                    new += 1;
+                    old += 1;
+                    self.manifold_event(CollisionPhase::Stay, new - 1)
+                }
+                Ordering::Greater => {
+                    old += 1;
+                    let (first, second) = self.previous_list[old - 1];
+                    CollisionEvent {
+                        phase: CollisionPhase::Exit,
+                        first,
+                        second,
+                        contact: None,
+                    }
+                }
+            };
+            self.events.push(event);
+        }
+        Ok(())
+    }
 
-            // so that binary search would actually work
-            self.colliding_list.par_sort_unstable();
+    fn manifold_event(&self, phase: CollisionPhase, index: usize) -> CollisionEvent {
+        let manifold = self.manifolds[index];
+        CollisionEvent {
+            phase,
+            first: manifold.first,
+            second: manifold.second,
+            contact: Some(manifold.contact),
         }
     }
 }
@@ -547,16 +1882,25 @@ pub struct Uniform {
     pub window_width: f32,
     /// read
     pub window_height: f32,
-    /// read
+    /// read, with a fixed update rate the end of the current tick rather than the frame
     pub utime: f32,
-    /// read
+    /// read, the start of the current tick
     pub last_utime: f32,
-    /// read
+    /// read, scaled by `time_scale` and 0 while paused, animations advance by this
     pub delta_time: f32,
     /// write
     pub global_offset_x: f32,
     /// write
     pub global_offset_y: f32,
+    /// write, 1 is normal speed
+    pub time_scale: f32,
+    /// write, non zero stops animations and `delta_time`
+    pub paused: u32,
+    /// read, unscaled wall clock delta for things like menus that keep running while paused
+    pub raw_delta_time: f32,
+    /// read, how far the drawn frame sits between the last two fixed ticks, always 1 without a
+    /// fixed update rate
+    pub alpha: f32,
 }
 
 #[derive(Clone, Copy)]
@@ -755,226 +2099,891 @@ impl KeyState {
     }
 }
 
-/// max sprites must exceed 32
-pub fn run(
+/// everything that can be wrong with a `RendererConfig`, checked before any window or device is created
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConfigError {
+    /// the renderer needs at least 32 sprite slots
+    TooFewSprites(u32),
+    /// half height resolution must be finite and positive
+    InvalidResolution(f32),
+    /// width and height must both be non zero
+    InvalidWindowSize(u32, u32),
+    /// every component must be within 0.0 to 1.0
+    InvalidClearColor(wgpu::Color),
+    /// the surface does not support the requested present mode
+    UnsupportedPresentMode(wgpu::PresentMode),
+    /// fixed delta time for headless rendering must be finite and positive
+    InvalidDeltaTime(f32),
+    /// fixed update rate must be finite and positive
+    InvalidUpdateRate(f32),
+    MissingFile(std::path::PathBuf),
+    /// at least one atlas page is needed
+    NoTexturePages,
+}
+impl std::fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::TooFewSprites(max_sprites) => {
+                write!(f, "max sprites must be at least 32, got {}", max_sprites)
+            }
+            Self::InvalidResolution(resolution) => {
+                write!(f, "invalid half height resolution {}", resolution)
+            }
+            Self::InvalidWindowSize(width, height) => {
+                write!(f, "invalid window size {}x{}", width, height)
+            }
+            Self::InvalidClearColor(color) => write!(f, "invalid clear color {:?}", color),
+            Self::UnsupportedPresentMode(mode) => {
+                write!(f, "present mode {:?} is not supported by the surface", mode)
+            }
+            Self::InvalidDeltaTime(delta_time) => write!(f, "invalid delta time {}", delta_time),
+            Self::InvalidUpdateRate(rate) => write!(f, "invalid fixed update rate {}", rate),
+            Self::MissingFile(path) => write!(f, "cannot find {}", path.display()),
+            Self::NoTexturePages => write!(f, "no atlas pages given"),
+        }
+    }
+}
+impl std::error::Error for ConfigError {}
+
+/// builder for everything `run` and `run_headless` used to hardcode, relative paths are resolved
+/// against the current directory
+#[derive(Debug, Clone)]
+pub struct RendererConfig {
     minimal_half_height_resolution: f32,
     max_sprites: u32,
-    entry_point: fn(&mut ecs::Table),
-    prep_func: fn(&mut ecs::Table),
-    post_func: fn(&mut ecs::Table),
-) {
-    assert!(max_sprites >= 32);
-    // utime
-    let start_time = std::time::Instant::now();
-    // window and event loop stuff
-    let event_loop = winit::event_loop::EventLoop::new();
-    let window = winit::window::Window::new(&event_loop).unwrap();
+    texture_paths: Vec<std::path::PathBuf>,
+    texture_description_path: std::path::PathBuf,
+    aseprite_paths: Vec<std::path::PathBuf>,
+    controller_paths: Vec<std::path::PathBuf>,
+    clear_color: wgpu::Color,
+    present_mode: wgpu::PresentMode,
+    power_preference: wgpu::PowerPreference,
+    window_title: String,
+    window_size: (u32, u32),
+    fixed_update_rate: Option<f32>,
+}
+impl Default for RendererConfig {
+    fn default() -> Self {
+        Self {
+            minimal_half_height_resolution: 200.0,
+            max_sprites: 1024,
+            texture_paths: vec!["res/texture.png".into()],
+            texture_description_path: "res/texture.json".into(),
+            aseprite_paths: vec![],
+            controller_paths: vec![],
+            clear_color: wgpu::Color {
+                r: 203.0 / 255.0,
+                g: 202.0 / 255.0,
+                b: 192.0 / 255.0,
+                a: 255.0 / 255.0,
+            },
+            present_mode: wgpu::PresentMode::Fifo,
+            power_preference: wgpu::PowerPreference::HighPerformance,
+            window_title: "renderer".into(),
+            window_size: (800, 600),
+            fixed_update_rate: None,
+        }
+    }
+}
+impl RendererConfig {
+    pub fn new() -> Self {
+        Self::default()
+    }
 
-    // wgpu prep stuff
-    let instance = wgpu::Instance::new(wgpu::InstanceDescriptor::default());
-    let surface = unsafe { instance.create_surface(&window).unwrap() };
-    let adapter = pollster::block_on(instance.request_adapter(&wgpu::RequestAdapterOptionsBase {
-        power_preference: wgpu::PowerPreference::HighPerformance,
-        force_fallback_adapter: false,
-        compatible_surface: Some(&surface),
-    }))
-    .unwrap();
-    let (device, queue) = pollster::block_on(adapter.request_device(
-        &wgpu::DeviceDescriptor {
-            label: None,
-            features: adapter.features(),
-            limits: adapter.limits(),
-        },
-        None,
-    ))
-    .unwrap();
-    let mut surface_config = wgpu::SurfaceConfiguration {
-        width: window.inner_size().width,
-        height: window.inner_size().height,
-        format: surface.get_capabilities(&adapter).formats[0],
-        usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
-        alpha_mode: surface.get_capabilities(&adapter).alpha_modes[0],
-        present_mode: wgpu::PresentMode::Fifo,
-        view_formats: vec![],
-    };
-    surface.configure(&device, &surface_config);
-
-    // loading texture and it's meta data
-    let current_dir = std::env::current_dir().unwrap();
-    let mut texture_dir = current_dir.clone();
-    texture_dir.push("res/texture.png");
-    let texture_data = image::io::Reader::open(texture_dir)
-        .unwrap()
-        .decode()
-        .unwrap()
-        .into_rgba8();
-    // todo, rearrange the image data into a long thin one if needed, according a loaded metadata file
-    // also maybe we can have in game editor that saves and expand on existing texture atlas
-    let pages_to_assign = 1;
-    let texture = device.create_texture(&wgpu::TextureDescriptor {
-        label: None,
-        size: wgpu::Extent3d {
-            width: texture_data.width(),
-            height: texture_data.height(),
-            depth_or_array_layers: pages_to_assign,
-        },
-        mip_level_count: 1,
-        sample_count: 1,
-        dimension: wgpu::TextureDimension::D2,
-        format: wgpu::TextureFormat::Rgba8UnormSrgb,
-        usage: wgpu::TextureUsages::COPY_DST
-            | wgpu::TextureUsages::COPY_SRC
-            | wgpu::TextureUsages::TEXTURE_BINDING,
-        view_formats: &[],
-    });
-    queue.write_texture(
-        wgpu::ImageCopyTextureBase {
-            texture: &texture,
-            mip_level: 0,
-            origin: wgpu::Origin3d::ZERO,
-            aspect: wgpu::TextureAspect::All,
-        },
-        &texture_data,
-        wgpu::ImageDataLayout {
-            offset: 0,
-            bytes_per_row: Some(texture_data.width() * 4),
-            rows_per_image: Some(texture_data.height() * 4),
-        },
-        wgpu::Extent3d {
-            width: texture_data.width(),
-            height: texture_data.height(),
-            depth_or_array_layers: 1,
-        },
-    );
-
-    // sound system todo
-
-    // font handler todo
-
-    // uniform data
-    let mut uniform_data = Uniform {
-        height_resolution: minimal_half_height_resolution,
-        texture_width: texture_data.width() as f32,
-        texture_height: texture_data.height() as f32,
-        window_width: 0.0,
-        window_height: 0.0,
-        utime: 0.0,
-        last_utime: 0.0,
-        delta_time: 0.0,
-        global_offset_x: 0.0,
-        global_offset_y: 0.0,
-    };
-    let uniform_buffer = device.create_buffer(&wgpu::BufferDescriptor {
-        label: None,
-        mapped_at_creation: false,
-        size: std::mem::size_of::<Uniform>() as u64,
-        usage: wgpu::BufferUsages::COPY_DST
-            | wgpu::BufferUsages::COPY_SRC
-            | wgpu::BufferUsages::UNIFORM,
-    });
-    queue.write_buffer(&uniform_buffer, 0, bytemuck::cast_slice(&[uniform_data]));
+    pub fn half_height_resolution(mut self, minimal_half_height_resolution: f32) -> Self {
+        self.minimal_half_height_resolution = minimal_half_height_resolution;
+        self
+    }
 
-    // storage buffer
-    let mut sprite_buffer = device.create_buffer(&wgpu::BufferDescriptor {
-        label: None,
-        mapped_at_creation: false,
-        size: std::mem::size_of::<Sprite>() as u64 * max_sprites as u64,
-        usage: wgpu::BufferUsages::COPY_DST
-            | wgpu::BufferUsages::COPY_SRC
-            | wgpu::BufferUsages::STORAGE,
-    });
-    let mut animation_buffer = device.create_buffer(&wgpu::BufferDescriptor {
-        label: None,
-        mapped_at_creation: false,
-        size: std::mem::size_of::<Animation>() as u64 * max_sprites as u64,
-        usage: wgpu::BufferUsages::COPY_DST
-            | wgpu::BufferUsages::COPY_SRC
-            | wgpu::BufferUsages::STORAGE,
-    });
+    /// slots allocated up front, they double whenever they run out, must be at least 32
+    pub fn max_sprites(mut self, max_sprites: u32) -> Self {
+        self.max_sprites = max_sprites;
+        self
+    }
 
-    // swap buffer
-    let mut swap_buffer = device.create_buffer(&wgpu::BufferDescriptor {
-        label: None,
-        mapped_at_creation: false,
-        size: std::mem::size_of::<Animation>() as u64 * max_sprites as u64,
-        usage: wgpu::BufferUsages::COPY_DST
-            | wgpu::BufferUsages::COPY_SRC
-            | wgpu::BufferUsages::STORAGE
-            | wgpu::BufferUsages::MAP_READ,
-    });
+    /// use a single atlas page
+    pub fn texture_path(mut self, path: impl Into<std::path::PathBuf>) -> Self {
+        self.texture_paths = vec![path.into()];
+        self
+    }
 
-    // depth texture for transparency sorting
-    let mut depth_texture = device.create_texture(&wgpu::TextureDescriptor {
-        label: None,
-        size: wgpu::Extent3d {
-            width: window.inner_size().width,
-            height: window.inner_size().height,
-            depth_or_array_layers: 1,
-        },
-        mip_level_count: 1,
-        sample_count: 1,
-        dimension: wgpu::TextureDimension::D2,
-        format: wgpu::TextureFormat::Depth32Float,
-        usage: wgpu::TextureUsages::COPY_DST
-            | wgpu::TextureUsages::COPY_SRC
-            | wgpu::TextureUsages::RENDER_ATTACHMENT,
-        view_formats: &[],
-    });
+    /// every image becomes a page of the atlas, `page` in the description file indexes this list,
+    /// images that are too large for the device are split into several layers on load
+    pub fn texture_pages<P: Into<std::path::PathBuf>>(
+        mut self,
+        paths: impl IntoIterator<Item = P>,
+    ) -> Self {
+        self.texture_paths = paths.into_iter().map(Into::into).collect();
+        self
+    }
 
-    // bind_group
-    let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-        label: None,
-        entries: &[
-            wgpu::BindGroupLayoutEntry {
-                binding: 0,
-                visibility: wgpu::ShaderStages::VERTEX_FRAGMENT,
-                ty: wgpu::BindingType::Buffer {
-                    ty: wgpu::BufferBindingType::Uniform,
-                    has_dynamic_offset: false,
-                    min_binding_size: None,
-                },
-                count: None,
-            },
-            wgpu::BindGroupLayoutEntry {
-                binding: 1,
-                visibility: wgpu::ShaderStages::VERTEX_FRAGMENT,
-                ty: wgpu::BindingType::Buffer {
-                    ty: wgpu::BufferBindingType::Storage { read_only: false },
-                    has_dynamic_offset: false,
-                    min_binding_size: None,
-                },
-                count: None,
-            },
-            wgpu::BindGroupLayoutEntry {
-                binding: 2,
-                visibility: wgpu::ShaderStages::VERTEX_FRAGMENT,
-                ty: wgpu::BindingType::Buffer {
-                    ty: wgpu::BufferBindingType::Storage { read_only: false },
-                    has_dynamic_offset: false,
-                    min_binding_size: None,
-                },
-                count: None,
+    pub fn texture_description_path(mut self, path: impl Into<std::path::PathBuf>) -> Self {
+        self.texture_description_path = path.into();
+        self
+    }
+
+    /// loads an aseprite json export next to the regular pages, its image becomes a page of its
+    /// own and every tag an entry named `<file stem>_<tag>`
+    ///
+    /// with `texture_pages` set to nothing the description file isn't needed at all
+    pub fn aseprite_sheet(mut self, path: impl Into<std::path::PathBuf>) -> Self {
+        self.aseprite_paths.push(path.into());
+        self
+    }
+
+    /// loads an animation controller file, it is added under its file stem
+    pub fn animation_controller(mut self, path: impl Into<std::path::PathBuf>) -> Self {
+        self.controller_paths.push(path.into());
+        self
+    }
+
+    pub fn clear_color(mut self, color: wgpu::Color) -> Self {
+        self.clear_color = color;
+        self
+    }
+
+    pub fn present_mode(mut self, present_mode: wgpu::PresentMode) -> Self {
+        self.present_mode = present_mode;
+        self
+    }
+
+    pub fn power_preference(mut self, power_preference: wgpu::PowerPreference) -> Self {
+        self.power_preference = power_preference;
+        self
+    }
+
+    pub fn window_title(mut self, title: impl Into<String>) -> Self {
+        self.window_title = title.into();
+        self
+    }
+
+    /// also the size of the offscreen canvas in headless mode
+    pub fn window_size(mut self, width: u32, height: u32) -> Self {
+        self.window_size = (width, height);
+        self
+    }
+
+    /// ticks the ecs this many times per second no matter the frame rate, zero or more times
+    /// per frame, sprites are drawn between their last two positions using `Uniform::alpha`
+    pub fn fixed_update_rate(mut self, ticks_per_sec: f32) -> Self {
+        self.fixed_update_rate = Some(ticks_per_sec);
+        self
+    }
+
+    /// checks everything that can be checked without a device, `run` calls this for you
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        if self.max_sprites < 32 {
+            return Err(ConfigError::TooFewSprites(self.max_sprites));
+        }
+        if !self.minimal_half_height_resolution.is_finite()
+            || self.minimal_half_height_resolution <= 0.0
+        {
+            return Err(ConfigError::InvalidResolution(
+                self.minimal_half_height_resolution,
+            ));
+        }
+        if self.window_size.0 == 0 || self.window_size.1 == 0 {
+            return Err(ConfigError::InvalidWindowSize(
+                self.window_size.0,
+                self.window_size.1,
+            ));
+        }
+        let color = self.clear_color;
+        if ![color.r, color.g, color.b, color.a]
+            .iter()
+            .all(|x| (0.0..=1.0).contains(x))
+        {
+            return Err(ConfigError::InvalidClearColor(color));
+        }
+        if let Some(rate) = self.fixed_update_rate {
+            if !rate.is_finite() || rate <= 0.0 {
+                return Err(ConfigError::InvalidUpdateRate(rate));
+            }
+        }
+        if self.texture_paths.is_empty() && self.aseprite_paths.is_empty() {
+            return Err(ConfigError::NoTexturePages);
+        }
+        let description_path =
+            (!self.texture_paths.is_empty()).then_some(&self.texture_description_path);
+        for path in self
+            .texture_paths
+            .iter()
+            .chain(description_path)
+            .chain(&self.aseprite_paths)
+            .chain(&self.controller_paths)
+        {
+            let path = self.resolve(path);
+            if !path.is_file() {
+                return Err(ConfigError::MissingFile(path));
+            }
+        }
+        Ok(())
+    }
+
+    fn resolve(&self, path: &std::path::Path) -> std::path::PathBuf {
+        std::env::current_dir().unwrap_or_default().join(path)
+    }
+}
+
+/// all the gpu resources and the ecs, shared by the windowed and the headless path
+///
+/// always kept behind a box, sprite master and collision manager hold raw pointers into it
+struct Renderer {
+    device: wgpu::Device,
+    queue: wgpu::Queue,
+
+    uniform_buffer: wgpu::Buffer,
+    sprite_buffer: wgpu::Buffer,
+    animation_buffer: wgpu::Buffer,
+
+    texture: wgpu::Texture,
+    depth_texture: wgpu::Texture,
+    bind_group_layout: wgpu::BindGroupLayout,
+    bind_group: wgpu::BindGroup,
+    pipeline: wgpu::RenderPipeline,
+    // slots the instance buffers have room for
+    capacity: u32,
+
+    uniform_data: Uniform,
+    clear_color: wgpu::Color,
+    // all the sprites, which is sortet then submitted to the storage buffer
+    sorted_sprites: Vec<Sprite>,
+    // seconds per tick with a fixed update rate
+    fixed_step: Option<f32>,
+    // time not yet simulated by fixed ticks
+    accumulator: f32,
+
+    ecs: ecs::ECS,
+}
+impl Renderer {
+    fn new(
+        adapter: &wgpu::Adapter,
+        format: wgpu::TextureFormat,
+        size: (u32, u32),
+        config: &RendererConfig,
+        entry_point: fn(&mut ecs::Table),
+    ) -> Result<Box<Self>, RendererError> {
+        let max_sprites = config.max_sprites;
+        let (device, queue) = pollster::block_on(adapter.request_device(
+            &wgpu::DeviceDescriptor {
+                label: None,
+                features: adapter.features(),
+                limits: adapter.limits(),
             },
-            wgpu::BindGroupLayoutEntry {
-                binding: 3,
-                visibility: wgpu::ShaderStages::VERTEX_FRAGMENT,
-                ty: wgpu::BindingType::Texture {
-                    sample_type: wgpu::TextureSampleType::Float { filterable: true },
-                    view_dimension: wgpu::TextureViewDimension::D2,
-                    multisampled: false,
+            None,
+        ))
+        .map_err(RendererError::RequestDevice)?;
+
+        // loading texture and it's meta data
+        let mut texture_paths: Vec<_> = config
+            .texture_paths
+            .iter()
+            .map(|path| config.resolve(path))
+            .collect();
+        let mut texture_map = if texture_paths.is_empty() {
+            std::collections::HashMap::new()
+        } else {
+            SpriteMaster3000::load_descriptions(&config.resolve(&config.texture_description_path))?
+        };
+        // every aseprite sheet brings its own page
+        for path in &config.aseprite_paths {
+            let (image_path, descriptions) = atlas::load_aseprite(config.resolve(path))?;
+            for (name, mut tex_data) in descriptions {
+                tex_data.page = texture_paths.len() as u32;
+                texture_map.insert(name, tex_data);
+            }
+            texture_paths.push(image_path);
+        }
+
+        let limits = device.limits();
+        let (layers, layouts) = load_atlas_pages(&texture_paths, limits.max_texture_dimension_2d)?;
+        if layers.len() as u32 > limits.max_texture_array_layers {
+            return Err(RendererError::TooManyAtlasPages {
+                pages: layers.len() as u32,
+                max: limits.max_texture_array_layers,
+            });
+        }
+        // points every entry at its layer in the texture array
+        for (name, tex_data) in texture_map.iter_mut() {
+            remap_to_layer(name, tex_data, &layouts)?;
+        }
+        // every layer of the array shares the same size, smaller pages just leave the rest empty
+        let texture_width = layers.iter().map(|x| x.width()).max().unwrap_or(1);
+        let texture_height = layers.iter().map(|x| x.height()).max().unwrap_or(1);
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: None,
+            size: wgpu::Extent3d {
+                width: texture_width,
+                height: texture_height,
+                depth_or_array_layers: layers.len() as u32,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::Rgba8UnormSrgb,
+            usage: wgpu::TextureUsages::COPY_DST
+                | wgpu::TextureUsages::COPY_SRC
+                | wgpu::TextureUsages::TEXTURE_BINDING,
+            view_formats: &[],
+        });
+        for (layer, texture_data) in layers.iter().enumerate() {
+            queue.write_texture(
+                wgpu::ImageCopyTextureBase {
+                    texture: &texture,
+                    mip_level: 0,
+                    origin: wgpu::Origin3d {
+                        x: 0,
+                        y: 0,
+                        z: layer as u32,
+                    },
+                    aspect: wgpu::TextureAspect::All,
+                },
+                texture_data,
+                wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: Some(texture_data.width() * 4),
+                    rows_per_image: Some(texture_data.height()),
                 },
-                count: None,
+                wgpu::Extent3d {
+                    width: texture_data.width(),
+                    height: texture_data.height(),
+                    depth_or_array_layers: 1,
+                },
+            );
+        }
+
+        // sound system todo
+
+        // font handler todo
+
+        // uniform data
+        let uniform_data = Uniform {
+            height_resolution: config.minimal_half_height_resolution,
+            texture_width: texture_width as f32,
+            texture_height: texture_height as f32,
+            window_width: size.0 as f32,
+            window_height: size.1 as f32,
+            utime: 0.0,
+            last_utime: 0.0,
+            delta_time: 0.0,
+            global_offset_x: 0.0,
+            global_offset_y: 0.0,
+            time_scale: 1.0,
+            paused: 0,
+            raw_delta_time: 0.0,
+            alpha: 1.0,
+        };
+        let uniform_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: None,
+            mapped_at_creation: false,
+            size: std::mem::size_of::<Uniform>() as u64,
+            usage: wgpu::BufferUsages::COPY_DST
+                | wgpu::BufferUsages::COPY_SRC
+                | wgpu::BufferUsages::UNIFORM,
+        });
+        queue.write_buffer(&uniform_buffer, 0, bytemuck::cast_slice(&[uniform_data]));
+
+        // storage buffers, they grow along with the animation slots up to what a single binding
+        // can hold
+        let (sprite_buffer, animation_buffer) = create_instance_buffers(&device, max_sprites);
+        let max_capacity = device.limits().max_storage_buffer_binding_size
+            / std::mem::size_of::<Sprite>().max(std::mem::size_of::<Animation>()) as u32;
+
+        // depth texture for transparency sorting
+        let depth_texture = create_depth_texture(&device, size.0, size.1);
+
+        // bind_group
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: None,
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::VERTEX_FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::VERTEX_FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Storage { read_only: false },
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::VERTEX_FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Storage { read_only: true },
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 3,
+                    visibility: wgpu::ShaderStages::VERTEX_FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::D2Array,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+            ],
+        });
+        let bind_group = create_bind_group(
+            &device,
+            &bind_group_layout,
+            &uniform_buffer,
+            &sprite_buffer,
+            &animation_buffer,
+            &texture,
+        );
+
+        // shader
+        let shader = device.create_shader_module(wgpu::include_wgsl!("./shader.wgsl"));
+
+        // render pipeline
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: None,
+            // you can have more bind groups, maybe that is how you switch out the buffers
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: None,
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: "vs_main",
+                buffers: &[],
             },
-        ],
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: Some(wgpu::DepthStencilState {
+                format: wgpu::TextureFormat::Depth32Float,
+                depth_write_enabled: true,
+                depth_compare: wgpu::CompareFunction::Less,
+                stencil: wgpu::StencilState::default(),
+                bias: wgpu::DepthBiasState::default(),
+            }),
+            multisample: wgpu::MultisampleState::default(),
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: "fs_main",
+                targets: &[Some(wgpu::ColorTargetState {
+                    format,
+                    blend: Some(wgpu::BlendState {
+                        color: wgpu::BlendComponent {
+                            src_factor: wgpu::BlendFactor::SrcAlpha,
+                            dst_factor: wgpu::BlendFactor::OneMinusSrcAlpha,
+                            operation: wgpu::BlendOperation::Add,
+                        },
+                        alpha: wgpu::BlendComponent {
+                            src_factor: wgpu::BlendFactor::Zero,
+                            dst_factor: wgpu::BlendFactor::One,
+                            operation: wgpu::BlendOperation::Add,
+                        },
+                    }),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            multiview: None,
+        });
+
+        let mut renderer = Box::new(Self {
+            device,
+            queue,
+            uniform_buffer,
+            sprite_buffer,
+            animation_buffer,
+            texture,
+            depth_texture,
+            bind_group_layout,
+            bind_group,
+            pipeline,
+            capacity: max_sprites,
+            uniform_data,
+            clear_color: config.clear_color,
+            sorted_sprites: Vec::with_capacity(max_sprites as usize),
+            fixed_step: config.fixed_update_rate.map(|rate| 1.0 / rate),
+            accumulator: 0.0,
+            ecs: ecs::ECS::new(entry_point),
+        });
+
+        // texture map data, pointing into the box so it stays valid when the box moves
+        let sprite_master = SpriteMaster3000::new(
+            texture_map,
+            max_sprites,
+            max_capacity.max(max_sprites),
+            &mut renderer.ecs.table,
+        )?;
+
+        // states resolve their textures against the ids the sprite master handed out
+        let mut controllers = AnimationControllers::new(sprite_master.texture_ids.clone());
+        for path in &config.controller_paths {
+            let path = config.resolve(path);
+            let name = path
+                .file_stem()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .into_owned();
+            controllers.add(name, controller::AnimationController::load(&path)?)?;
+        }
+
+        let collision_manager = CollisionManager::new(&mut renderer.ecs.table);
+
+        // ecs prep work
+        let table = &mut renderer.ecs.table;
+        table.add_state(uniform_data)?;
+        table.add_state(RunningState::Running)?;
+        table.add_state(sprite_master)?;
+        table.add_state(collision_manager)?;
+        table.add_state(MouseState {
+            x: 0.0,
+            y: 0.0,
+
+            left: false,
+            left_clicked: false,
+            left_released: false,
+
+            middle: false,
+            middle_clicked: false,
+            middle_released: false,
+
+            right: false,
+            right_clicked: false,
+            right_released: false,
+
+            wheel_delta: 0.0,
+            in_screen: true,
+            just_entered: false,
+            just_left: false,
+        })?;
+        table.add_state(winit::keyboard::ModifiersState::empty())?;
+        table.add_state(KeyState::new())?;
+        table.add_state(AnimationEvents::default())?;
+        table.add_state(controllers)?;
+        table.register_column::<Sprite>();
+        table.register_column::<CollisionRect>();
+
+        Ok(renderer)
+    }
+
+    fn resize(&mut self, width: u32, height: u32) {
+        self.depth_texture = create_depth_texture(&self.device, width, height);
+        self.uniform_data.window_width = width as f32;
+        self.uniform_data.window_height = height as f32;
+    }
+
+    /// ticks the ecs once and draws the result into `target`
+    fn frame(&mut self, utime: f32, target: &wgpu::TextureView) -> Result<(), RendererError> {
+        self.uniform_data.utime = utime;
+        let raw_delta_time = self.uniform_data.utime - self.uniform_data.last_utime;
+        match self.fixed_step {
+            // ticks keep running while paused, only their scaled delta drops to 0, so systems can
+            // still unpause
+            Some(step) => {
+                // time simulated so far, each tick covers the next `step` of it
+                let mut tick_time = self.uniform_data.last_utime - self.accumulator;
+                self.accumulator += raw_delta_time;
+                let mut ticks = 0;
+                while self.accumulator >= step && ticks < MAX_TICKS_PER_FRAME {
+                    self.accumulator -= step;
+                    ticks += 1;
+                    self.simulate(tick_time, step * self.time_scale(), step)?;
+                    tick_time += step;
+                }
+                // too far behind to catch up, drop the rest instead of spiraling
+                if ticks == MAX_TICKS_PER_FRAME {
+                    self.accumulator = self.accumulator.min(step);
+                }
+                self.uniform_data.alpha = (self.accumulator / step).clamp(0.0, 1.0);
+            }
+            None => {
+                self.simulate(
+                    self.uniform_data.last_utime,
+                    raw_delta_time * self.time_scale(),
+                    raw_delta_time,
+                )?;
+                self.uniform_data.alpha = 1.0;
+            }
+        }
+        self.ecs.table.read_state::<Uniform>()?.alpha = self.uniform_data.alpha;
+        // after ticking we can adjust the last_utime
+        self.uniform_data.last_utime = self.uniform_data.utime;
+
+        self.render(target)
+    }
+
+    /// 0 while paused, set by the last tick
+    fn time_scale(&self) -> f32 {
+        if self.uniform_data.paused != 0 {
+            0.0
+        } else {
+            self.uniform_data.time_scale.max(0.0)
+        }
+    }
+
+    /// one tick of the ecs together with everything that has to stay in step with it, the tick
+    /// starts at `last_utime` and covers the unscaled `raw_delta_time`
+    fn simulate(
+        &mut self,
+        last_utime: f32,
+        delta_time: f32,
+        raw_delta_time: f32,
+    ) -> Result<(), RendererError> {
+        // local uniform -> table uniform
+        self.uniform_data.delta_time = delta_time;
+        self.uniform_data.raw_delta_time = raw_delta_time;
+        let uni = &mut *self.ecs.table.read_state::<Uniform>()?;
+        uni.utime = last_utime + raw_delta_time;
+        uni.window_width = self.uniform_data.window_width;
+        uni.window_height = self.uniform_data.window_height;
+        uni.delta_time = self.uniform_data.delta_time;
+        uni.raw_delta_time = self.uniform_data.raw_delta_time;
+        uni.last_utime = last_utime;
+        uni.alpha = self.uniform_data.alpha;
+        let uni = *uni;
+
+        // positions before this tick, drawing blends from them to the new ones
+        let sprites = unsafe { self.ecs.table.read_column::<Sprite>()? };
+        for sprite in sprites.iter_mut() {
+            sprite.prev_pos_x = sprite.pos_x;
+            sprite.prev_pos_y = sprite.pos_y;
+        }
+        let sprites = sprites as *const [Sprite];
+
+        // collision handling
+        self.ecs.table.read_state::<CollisionManager>()?.update()?;
+
+        // animations advance once per sprite here, so this tick already sees the frame events
+        self.ecs
+            .table
+            .read_state::<SpriteMaster3000>()?
+            .step_animations(unsafe { &*sprites }, &uni)?;
+
+        // ecs ticking
+        self.ecs.tick();
+
+        // parameters set during the tick decide the states drawn next frame
+        self.ecs
+            .table
+            .read_state::<SpriteMaster3000>()?
+            .run_controllers()?;
+
+        // reset some states after ticking
+        self.ecs.table.read_state::<KeyState>()?.reset();
+        self.ecs.table.read_state::<MouseState>()?.reset();
+        self.ecs.table.read_state::<AnimationEvents>()?.reset();
+
+        // table uniform -> local uniform
+        let uni = self.ecs.table.read_state::<Uniform>()?;
+        self.uniform_data.height_resolution = uni.height_resolution;
+        self.uniform_data.global_offset_x = uni.global_offset_x;
+        self.uniform_data.global_offset_y = uni.global_offset_y;
+        self.uniform_data.time_scale = uni.time_scale;
+        self.uniform_data.paused = uni.paused;
+        Ok(())
+    }
+
+    /// draws the current state into `target`, positions blended by the interpolation alpha
+    fn render(&mut self, target: &wgpu::TextureView) -> Result<(), RendererError> {
+        // slots were added since the last frame, the old buffers and bind group can't hold them
+        let sprite_master = self.ecs.table.read_state::<SpriteMaster3000>()?;
+        if sprite_master.capacity() > self.capacity {
+            self.capacity = sprite_master.capacity();
+            (self.sprite_buffer, self.animation_buffer) =
+                create_instance_buffers(&self.device, self.capacity);
+            self.bind_group = create_bind_group(
+                &self.device,
+                &self.bind_group_layout,
+                &self.uniform_buffer,
+                &self.sprite_buffer,
+                &self.animation_buffer,
+                &self.texture,
+            );
+        }
+        // the shader picks the frames from this copy
+        self.queue.write_buffer(
+            &self.animation_buffer,
+            0,
+            bytemuck::cast_slice(&sprite_master.anim_data),
+        );
+
+        let sprites = unsafe { self.ecs.table.read_column::<Sprite>()? };
+        self.sorted_sprites.clear();
+        self.sorted_sprites
+            .extend(sprites.iter().map(Sprite::shallow_copy));
+        let alpha = self.uniform_data.alpha;
+        if alpha < 1.0 {
+            for sprite in self.sorted_sprites.iter_mut() {
+                sprite.pos_x = sprite.prev_pos_x + (sprite.pos_x - sprite.prev_pos_x) * alpha;
+                sprite.pos_y = sprite.prev_pos_y + (sprite.pos_y - sprite.prev_pos_y) * alpha;
+            }
+        }
+        self.sorted_sprites.par_sort_unstable_by(|x, y| {
+            if x.base_depth == 0.5 && y.base_depth == 0.5 {
+                (y.pos_y - y.origin).total_cmp(&(x.pos_y - x.origin))
+            } else {
+                y.base_depth.total_cmp(&x.base_depth)
+            }
+        });
+        self.queue.write_buffer(
+            &self.sprite_buffer,
+            0,
+            bytemuck::cast_slice(unsafe {
+                from_raw_parts(
+                    self.sorted_sprites.as_ptr() as *const u8,
+                    self.sorted_sprites.len() * std::mem::size_of::<Sprite>(),
+                )
+            }),
+        );
+
+        // write uniform buffer
+        self.queue.write_buffer(
+            &self.uniform_buffer,
+            0,
+            bytemuck::cast_slice(&[self.uniform_data]),
+        );
+
+        // create encoder
+        let mut encoder = self
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+        let depth_view = self
+            .depth_texture
+            .create_view(&wgpu::TextureViewDescriptor::default());
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: None,
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: target,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(self.clear_color),
+                    // the headless path reads the target back, so the result has to be kept around
+                    store: true,
+                },
+            })],
+            depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                view: &depth_view,
+                depth_ops: Some(wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(1.0),
+                    store: true,
+                }),
+                stencil_ops: None,
+            }),
+        });
+        render_pass.set_pipeline(&self.pipeline);
+        render_pass.set_bind_group(0, &self.bind_group, &[]);
+        render_pass.draw(0..self.sorted_sprites.len() as u32 * 6, 0..1);
+        drop(render_pass);
+        // submit all the changes in this frame
+        self.queue.submit(Some(encoder.finish()));
+        Ok(())
+    }
+
+    /// copies a rgba8 texture into host memory, rows in wgpu copies are padded so they get stripped here
+    fn read_texture(&self, texture: &wgpu::Texture, width: u32, height: u32) -> image::RgbaImage {
+        let unpadded_bytes_per_row = width * 4;
+        let padded_bytes_per_row = (unpadded_bytes_per_row + wgpu::COPY_BYTES_PER_ROW_ALIGNMENT
+            - 1)
+            / wgpu::COPY_BYTES_PER_ROW_ALIGNMENT
+            * wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
+        let output_buffer = self.device.create_buffer(&wgpu::BufferDescriptor {
+            label: None,
+            mapped_at_creation: false,
+            size: padded_bytes_per_row as u64 * height as u64,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+        });
+
+        let mut encoder = self
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+        encoder.copy_texture_to_buffer(
+            wgpu::ImageCopyTexture {
+                texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d::ZERO,
+                aspect: wgpu::TextureAspect::All,
+            },
+            wgpu::ImageCopyBuffer {
+                buffer: &output_buffer,
+                layout: wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: Some(padded_bytes_per_row),
+                    rows_per_image: Some(height),
+                },
+            },
+            wgpu::Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+        );
+        self.queue.submit(Some(encoder.finish()));
+
+        output_buffer
+            .slice(..)
+            .map_async(wgpu::MapMode::Read, |x| {});
+        self.device.poll(wgpu::MaintainBase::Wait);
+        let mut pixels = Vec::with_capacity((unpadded_bytes_per_row * height) as usize);
+        for row in output_buffer
+            .slice(..)
+            .get_mapped_range()
+            .chunks(padded_bytes_per_row as usize)
+        {
+            pixels.extend_from_slice(&row[..unpadded_bytes_per_row as usize]);
+        }
+        output_buffer.unmap();
+
+        image::RgbaImage::from_raw(width, height, pixels).unwrap()
+    }
+}
+
+/// sprite and animation storage buffers with room for `capacity` slots
+fn create_instance_buffers(device: &wgpu::Device, capacity: u32) -> (wgpu::Buffer, wgpu::Buffer) {
+    let sprite_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+        label: None,
+        mapped_at_creation: false,
+        size: std::mem::size_of::<Sprite>() as u64 * capacity as u64,
+        usage: wgpu::BufferUsages::COPY_DST
+            | wgpu::BufferUsages::COPY_SRC
+            | wgpu::BufferUsages::STORAGE,
+    });
+    // animations are stepped on the cpu, the shader only reads the current frames
+    let animation_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+        label: None,
+        mapped_at_creation: false,
+        size: std::mem::size_of::<Animation>() as u64 * capacity as u64,
+        usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::STORAGE,
     });
-    let mut bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+    (sprite_buffer, animation_buffer)
+}
+
+/// called again whenever the instance buffers grow
+fn create_bind_group(
+    device: &wgpu::Device,
+    layout: &wgpu::BindGroupLayout,
+    uniform_buffer: &wgpu::Buffer,
+    sprite_buffer: &wgpu::Buffer,
+    animation_buffer: &wgpu::Buffer,
+    texture: &wgpu::Texture,
+) -> wgpu::BindGroup {
+    device.create_bind_group(&wgpu::BindGroupDescriptor {
         label: None,
-        layout: &bind_group_layout,
+        layout,
         entries: &[
             wgpu::BindGroupEntry {
                 binding: 0,
                 resource: wgpu::BindingResource::Buffer(wgpu::BufferBinding {
-                    buffer: &uniform_buffer,
+                    buffer: uniform_buffer,
                     offset: 0,
                     size: None,
                 }),
@@ -982,7 +2991,7 @@ pub fn run(
             wgpu::BindGroupEntry {
                 binding: 1,
                 resource: wgpu::BindingResource::Buffer(wgpu::BufferBinding {
-                    buffer: &sprite_buffer,
+                    buffer: sprite_buffer,
                     offset: 0,
                     size: None,
                 }),
@@ -990,7 +2999,7 @@ pub fn run(
             wgpu::BindGroupEntry {
                 binding: 2,
                 resource: wgpu::BindingResource::Buffer(wgpu::BufferBinding {
-                    buffer: &animation_buffer,
+                    buffer: animation_buffer,
                     offset: 0,
                     size: None,
                 }),
@@ -1001,7 +3010,8 @@ pub fn run(
                     &wgpu::TextureViewDescriptor {
                         label: None,
                         format: None,
-                        dimension: None,
+                        // a single page would otherwise default to a plain 2d view
+                        dimension: Some(wgpu::TextureViewDimension::D2Array),
                         aspect: wgpu::TextureAspect::All,
                         base_mip_level: 0,
                         mip_level_count: None,
@@ -1011,115 +3021,87 @@ pub fn run(
                 )),
             },
         ],
-    });
-
-    // shader
-    let shader = device.create_shader_module(wgpu::include_wgsl!("./shader.wgsl"));
+    })
+}
 
-    // render pipeline
-    let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
-        label: None,
-        // you can have more bind groups, maybe that is how you switch out the buffers
-        bind_group_layouts: &[&bind_group_layout],
-        push_constant_ranges: &[],
-    });
-    let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+fn create_depth_texture(device: &wgpu::Device, width: u32, height: u32) -> wgpu::Texture {
+    device.create_texture(&wgpu::TextureDescriptor {
         label: None,
-        layout: Some(&pipeline_layout),
-        vertex: wgpu::VertexState {
-            module: &shader,
-            entry_point: "vs_main",
-            buffers: &[],
+        size: wgpu::Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
         },
-        primitive: wgpu::PrimitiveState::default(),
-        depth_stencil: Some(wgpu::DepthStencilState {
-            format: wgpu::TextureFormat::Depth32Float,
-            depth_write_enabled: true,
-            depth_compare: wgpu::CompareFunction::Less,
-            stencil: wgpu::StencilState::default(),
-            bias: wgpu::DepthBiasState::default(),
-        }),
-        multisample: wgpu::MultisampleState::default(),
-        fragment: Some(wgpu::FragmentState {
-            module: &shader,
-            entry_point: "fs_main",
-            targets: &[Some(wgpu::ColorTargetState {
-                format: surface.get_capabilities(&adapter).formats[0],
-                blend: Some(wgpu::BlendState {
-                    color: wgpu::BlendComponent {
-                        src_factor: wgpu::BlendFactor::SrcAlpha,
-                        dst_factor: wgpu::BlendFactor::OneMinusSrcAlpha,
-                        operation: wgpu::BlendOperation::Add,
-                    },
-                    alpha: wgpu::BlendComponent {
-                        src_factor: wgpu::BlendFactor::Zero,
-                        dst_factor: wgpu::BlendFactor::One,
-                        operation: wgpu::BlendOperation::Add,
-                    },
-                }),
-                write_mask: wgpu::ColorWrites::ALL,
-            })],
-        }),
-        multiview: None,
-    });
-
-    // ecs
-    let mut ecs = ecs::ECS::new(entry_point);
-
-    // all the sprites, which is sortet then submitted to the storage buffer
-    let mut sorted_sprites: Vec<Sprite> = Vec::with_capacity(max_sprites as usize);
-
-    // texture map data
-    let sprite_master = SpriteMaster3000::new(
-        current_dir.clone(),
-        max_sprites,
-        &mut ecs.table,
-        &queue,
-        &animation_buffer,
-        vec![Animation::new_empty(); max_sprites as usize],
-    );
-
-    let collision_manager = CollisionManager::new(&mut ecs.table);
-
-    // ecs prep work
-    ecs.table.add_state(uniform_data).unwrap();
-    ecs.table.add_state(RunningState::Running).unwrap();
-    ecs.table.add_state(sprite_master).unwrap();
-    ecs.table.add_state(collision_manager).unwrap();
-    ecs.table
-        .add_state(MouseState {
-            x: 0.0,
-            y: 0.0,
-
-            left: false,
-            left_clicked: false,
-            left_released: false,
-
-            middle: false,
-            middle_clicked: false,
-            middle_released: false,
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format: wgpu::TextureFormat::Depth32Float,
+        usage: wgpu::TextureUsages::COPY_DST
+            | wgpu::TextureUsages::COPY_SRC
+            | wgpu::TextureUsages::RENDER_ATTACHMENT,
+        view_formats: &[],
+    })
+}
 
-            right: false,
-            right_clicked: false,
-            right_released: false,
+/// the config is validated before anything is created, only returns if it is invalid
+pub fn run(
+    config: RendererConfig,
+    entry_point: fn(&mut ecs::Table),
+    prep_func: fn(&mut ecs::Table),
+    post_func: fn(&mut ecs::Table),
+) -> Result<(), RendererError> {
+    config.validate()?;
+    // utime
+    let start_time = std::time::Instant::now();
+    // window and event loop stuff
+    let event_loop = winit::event_loop::EventLoop::new();
+    let window = winit::window::WindowBuilder::new()
+        .with_title(config.window_title.as_str())
+        .with_inner_size(winit::dpi::PhysicalSize::new(
+            config.window_size.0,
+            config.window_size.1,
+        ))
+        .build(&event_loop)
+        .map_err(RendererError::Window)?;
 
-            wheel_delta: 0.0,
-            in_screen: true,
-            just_entered: false,
-            just_left: false,
-        })
-        .unwrap();
-    ecs.table
-        .add_state(winit::keyboard::ModifiersState::empty())
-        .unwrap();
-    ecs.table.add_state(KeyState::new()).unwrap();
-    ecs.table.register_column::<Sprite>();
-    ecs.table.register_column::<CollisionRect>();
+    // wgpu prep stuff
+    let instance = wgpu::Instance::new(wgpu::InstanceDescriptor::default());
+    let surface =
+        unsafe { instance.create_surface(&window) }.map_err(RendererError::CreateSurface)?;
+    let adapter = pollster::block_on(instance.request_adapter(&wgpu::RequestAdapterOptionsBase {
+        power_preference: config.power_preference,
+        force_fallback_adapter: false,
+        compatible_surface: Some(&surface),
+    }))
+    .ok_or(RendererError::NoAdapter)?;
+    let capabilities = surface.get_capabilities(&adapter);
+    if !capabilities.present_modes.contains(&config.present_mode) {
+        return Err(ConfigError::UnsupportedPresentMode(config.present_mode).into());
+    }
+    let format = capabilities.formats[0];
+    let mut renderer = Renderer::new(
+        &adapter,
+        format,
+        (window.inner_size().width, window.inner_size().height),
+        &config,
+        entry_point,
+    )?;
+    let mut surface_config = wgpu::SurfaceConfiguration {
+        width: window.inner_size().width,
+        height: window.inner_size().height,
+        format,
+        usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+        alpha_mode: capabilities.alpha_modes[0],
+        present_mode: config.present_mode,
+        view_formats: vec![],
+    };
+    surface.configure(&renderer.device, &surface_config);
 
     // custom prep work done to ecs
-    (prep_func)(&mut ecs.table);
+    (prep_func)(&mut renderer.ecs.table);
 
     event_loop.run(move |event, _, control_flow| {
+        let ecs = &mut renderer.ecs;
         match *ecs.table.read_state::<RunningState>().unwrap() {
             RunningState::Running => control_flow.set_poll(),
             RunningState::Closed => {
@@ -1132,125 +3114,30 @@ pub fn run(
                 (post_func)(&mut ecs.table);
             }
             winit::event::Event::RedrawRequested(_) => {
-                // local uniform -> table uniform
-                uniform_data.utime = start_time.elapsed().as_secs_f32();
-                uniform_data.delta_time = uniform_data.utime - uniform_data.last_utime;
-                let uni = &mut *ecs.table.read_state::<Uniform>().unwrap();
-                uni.utime = uniform_data.utime;
-                uni.window_width = uniform_data.window_width;
-                uni.window_height = uniform_data.window_height;
-                uni.delta_time = uniform_data.delta_time;
-                uni.last_utime = uniform_data.last_utime;
-
-                // collision handling
-                // let collision_rects = ecs.table.read_column::<CollisionRect>().unwrap();
-                ecs.table.read_state::<CollisionManager>().unwrap().update();
-                // drop(collision_rects);
-
-                // depth sorting before ticking to prevent jankness since changing animation state has weirdness on the data flowing back from gpu
-                // note that this slice ma
-                let sprites = unsafe { ecs.table.read_column::<Sprite>().unwrap() };
-                unsafe {
-                    sorted_sprites.set_len(sprites.len());
-                    std::ptr::copy(sprites.as_ptr(), sorted_sprites.as_mut_ptr(), sprites.len());
-                };
-                sorted_sprites[0..sprites.len()].par_sort_unstable_by(|x, y| {
-                    if x.base_depth == 0.5 && y.base_depth == 0.5 {
-                        (y.pos_y - y.origin).total_cmp(&(x.pos_y - x.origin))
-                    } else {
-                        y.base_depth.total_cmp(&x.base_depth)
+                let canvas = match surface.get_current_texture().map_err(RendererError::from) {
+                    Ok(canvas) => canvas,
+                    // reconfigure and try again on the next frame
+                    Err(RendererError::SurfaceLost | RendererError::SurfaceOutdated) => {
+                        surface.configure(&renderer.device, &surface_config);
+                        return;
                     }
-                });
-                queue.write_buffer(
-                    &sprite_buffer,
-                    0,
-                    bytemuck::cast_slice(unsafe {
-                        from_raw_parts(
-                            sorted_sprites.as_ptr() as *const u8,
-                            sprites.len() * std::mem::size_of::<Sprite>(),
-                        )
-                    }),
-                );
-                drop(sprites);
-
-                // ecs ticking
-                ecs.tick();
-
-                // reset some states after ticking
-                ecs.table.read_state::<KeyState>().unwrap().reset();
-                ecs.table.read_state::<MouseState>().unwrap().reset();
-
-                // uniform stuff
-                // after ticking we can adjust the last_utime
-                uniform_data.last_utime = uniform_data.utime;
-                // table uniform -> local uniform
-                let uni = ecs.table.read_state::<Uniform>().unwrap();
-                uniform_data.height_resolution = uni.height_resolution;
-                uniform_data.global_offset_x = uni.global_offset_x;
-                uniform_data.global_offset_y = uni.global_offset_y;
-                // write uniform buffer
-                queue.write_buffer(&uniform_buffer, 0, bytemuck::cast_slice(&[uniform_data]));
-
-                // create encoder
-                let mut encoder =
-                    device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
-                // copying buffer
-                encoder.copy_buffer_to_buffer(
-                    &animation_buffer,
-                    0,
-                    &swap_buffer,
-                    0,
-                    animation_buffer.size(),
-                );
-                let canvas = surface.get_current_texture().unwrap();
+                    Err(RendererError::Surface(wgpu::SurfaceError::Timeout)) => return,
+                    Err(err) => {
+                        eprintln!("{}", err);
+                        control_flow.set_exit();
+                        return;
+                    }
+                };
                 let canvas_view = canvas
                     .texture
                     .create_view(&wgpu::TextureViewDescriptor::default());
-                let depth_view = depth_texture.create_view(&wgpu::TextureViewDescriptor::default());
-                let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
-                    label: None,
-                    color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                        view: &canvas_view,
-                        resolve_target: None,
-                        ops: wgpu::Operations {
-                            load: wgpu::LoadOp::Clear(wgpu::Color {
-                                r: 203.0 / 255.0,
-                                g: 202.0 / 255.0,
-                                b: 192.0 / 255.0,
-                                a: 255.0 / 255.0,
-                            }),
-                            store: false,
-                        },
-                    })],
-                    depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
-                        view: &depth_view,
-                        depth_ops: Some(wgpu::Operations {
-                            load: wgpu::LoadOp::Clear(1.0),
-                            store: true,
-                        }),
-                        stencil_ops: None,
-                    }),
-                });
-                render_pass.set_pipeline(&pipeline);
-                render_pass.set_bind_group(0, &bind_group, &[]);
-                render_pass.draw(0..sorted_sprites.len() as u32 * 6, 0..1);
-                drop(render_pass);
-                // submit all the changes in this frame
-                queue.submit(Some(encoder.finish()));
+                let result = renderer.frame(start_time.elapsed().as_secs_f32(), &canvas_view);
                 // present the result
                 canvas.present();
-
-                // mapping the buffer, after waiting for it to map copy the content to the host side of the buffer, then finally unmap it
-                swap_buffer.slice(..).map_async(wgpu::MapMode::Read, |x| {});
-                device.poll(wgpu::MaintainBase::Wait);
-                ecs.table
-                    .read_state::<SpriteMaster3000>()
-                    .unwrap()
-                    .anim_data
-                    .clone_from_slice(bytemuck::cast_slice::<u8, Animation>(
-                        &swap_buffer.slice(..).get_mapped_range()[..],
-                    ));
-                swap_buffer.unmap();
+                if let Err(err) = result {
+                    eprintln!("{}", err);
+                    control_flow.set_exit();
+                }
             }
             winit::event::Event::WindowEvent { event, window_id } => {
                 if window_id == window.id() {
@@ -1258,25 +3145,8 @@ pub fn run(
                         winit::event::WindowEvent::Resized(new_size) => {
                             surface_config.height = new_size.height;
                             surface_config.width = new_size.width;
-                            surface.configure(&device, &surface_config);
-                            depth_texture = device.create_texture(&wgpu::TextureDescriptor {
-                                label: None,
-                                size: wgpu::Extent3d {
-                                    width: new_size.width,
-                                    height: new_size.height,
-                                    depth_or_array_layers: 1,
-                                },
-                                mip_level_count: 1,
-                                sample_count: 1,
-                                dimension: wgpu::TextureDimension::D2,
-                                format: wgpu::TextureFormat::Depth32Float,
-                                usage: wgpu::TextureUsages::COPY_DST
-                                    | wgpu::TextureUsages::COPY_SRC
-                                    | wgpu::TextureUsages::RENDER_ATTACHMENT,
-                                view_formats: &[],
-                            });
-                            uniform_data.window_width = new_size.width as f32;
-                            uniform_data.window_height = new_size.height as f32;
+                            surface.configure(&renderer.device, &surface_config);
+                            renderer.resize(new_size.width, new_size.height);
                             window.request_redraw();
                         }
                         winit::event::WindowEvent::CloseRequested => {
@@ -1381,3 +3251,398 @@ pub fn run(
         }
     })
 }
+
+/// same pipeline as `run` but without a window or a surface, the ecs is ticked `frames` times with a
+/// fixed `delta_time` and every frame is rendered offscreen and saved as `frame_00000.png` and so on
+/// into `output_dir`, falls back to a software adapter if there is no hardware one
+///
+/// the window size of the config is used as the size of the offscreen canvas
+pub fn run_headless(
+    config: RendererConfig,
+    frames: u32,
+    delta_time: f32,
+    output_dir: impl AsRef<std::path::Path>,
+    entry_point: fn(&mut ecs::Table),
+    prep_func: fn(&mut ecs::Table),
+    post_func: fn(&mut ecs::Table),
+) -> Result<(), RendererError> {
+    config.validate()?;
+    if !delta_time.is_finite() || delta_time <= 0.0 {
+        return Err(ConfigError::InvalidDeltaTime(delta_time).into());
+    }
+    let size = config.window_size;
+    let output_dir = output_dir.as_ref();
+    std::fs::create_dir_all(output_dir).map_err(|err| RendererError::AssetIo {
+        path: output_dir.to_path_buf(),
+        source: err,
+    })?;
+
+    // wgpu prep stuff, no surface to be compatible with
+    let instance = wgpu::Instance::new(wgpu::InstanceDescriptor::default());
+    let adapter = pollster::block_on(instance.request_adapter(&wgpu::RequestAdapterOptionsBase {
+        power_preference: config.power_preference,
+        force_fallback_adapter: false,
+        compatible_surface: None,
+    }))
+    .or_else(|| {
+        pollster::block_on(instance.request_adapter(&wgpu::RequestAdapterOptionsBase {
+            power_preference: config.power_preference,
+            force_fallback_adapter: true,
+            compatible_surface: None,
+        }))
+    })
+    .ok_or(RendererError::NoAdapter)?;
+    let format = wgpu::TextureFormat::Rgba8UnormSrgb;
+    let mut renderer = Renderer::new(&adapter, format, size, &config, entry_point)?;
+
+    // the offscreen canvas
+    let canvas = renderer.device.create_texture(&wgpu::TextureDescriptor {
+        label: None,
+        size: wgpu::Extent3d {
+            width: size.0,
+            height: size.1,
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format,
+        usage: wgpu::TextureUsages::COPY_SRC | wgpu::TextureUsages::RENDER_ATTACHMENT,
+        view_formats: &[],
+    });
+    let canvas_view = canvas.create_view(&wgpu::TextureViewDescriptor::default());
+
+    // custom prep work done to ecs
+    (prep_func)(&mut renderer.ecs.table);
+
+    for frame_index in 0..frames {
+        if let RunningState::Closed = *renderer.ecs.table.read_state::<RunningState>()? {
+            break;
+        }
+        // time only ever advances by the fixed step so the output is reproducible
+        renderer.frame((frame_index + 1) as f32 * delta_time, &canvas_view)?;
+        let path = output_dir.join(format!("frame_{:05}.png", frame_index));
+        renderer
+            .read_texture(&canvas, size.0, size.1)
+            .save(&path)
+            .map_err(|err| RendererError::ImageSave { path, source: err })?;
+    }
+
+    (post_func)(&mut renderer.ecs.table);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SWITCHES: usize = 5000;
+
+    fn texture(tex_x: u32, frames: u32) -> TextureDescription {
+        TextureDescription {
+            tex_x,
+            tex_width: 16,
+            tex_height: 16,
+            frames,
+            looping: 1,
+            frames_per_sec: 10,
+            ..Default::default()
+        }
+    }
+
+    /// a sprite master with only a few slots that can't grow, so any leak runs out of them
+    fn sprite_master(table: &mut ecs::Table) -> SpriteMaster3000<'_> {
+        let map = [
+            ("idle".to_string(), texture(0, 4)),
+            ("run".to_string(), texture(64, 6)),
+        ]
+        .into_iter()
+        .collect();
+        table.register_column::<Sprite>();
+        table.add_state(AnimationEvents::default()).unwrap();
+        SpriteMaster3000::new(map, 4, 4, table).unwrap()
+    }
+
+    #[test]
+    fn change_state_to_same_texture_keeps_slot() {
+        let mut table = Box::new(ecs::ECS::new(|_| {}).table);
+        let mut master = sprite_master(&mut table);
+        let mut sprite = master.add_sprite("idle", (0.0, 0.0), 0.0).unwrap();
+        let slot = sprite.anim_buffer_index;
+        for _ in 0..SWITCHES {
+            master.change_state(&mut sprite, "idle").unwrap();
+            assert_eq!(master.slot_stats().used, 1);
+        }
+        assert_eq!(sprite.anim_buffer_index, slot);
+    }
+
+    #[test]
+    fn instant_switch_reuses_slot() {
+        let mut table = Box::new(ecs::ECS::new(|_| {}).table);
+        let mut master = sprite_master(&mut table);
+        let mut sprite = master.add_sprite("idle", (0.0, 0.0), 0.0).unwrap();
+        for each in 0..SWITCHES {
+            let texture = if each % 2 == 0 { "run" } else { "idle" };
+            master.change_state(&mut sprite, texture).unwrap();
+            assert_eq!(master.slot_stats().used, 1);
+            assert_eq!(master.get_name(&sprite).unwrap(), texture);
+        }
+        assert_eq!(master.slot_stats().high_water, 1);
+    }
+
+    #[test]
+    fn crossfade_frees_outgoing_slot() {
+        let mut table = Box::new(ecs::ECS::new(|_| {}).table);
+        let table = &mut *table as *mut ecs::Table;
+        let mut master = sprite_master(unsafe { &mut *table });
+        let mut sprite = master.add_sprite("idle", (0.0, 0.0), 0.0).unwrap();
+        let mut uniform: Uniform = bytemuck::Zeroable::zeroed();
+        uniform.delta_time = 0.2;
+        for each in 0..SWITCHES {
+            let texture = if each % 2 == 0 { "run" } else { "idle" };
+            master
+                .change_state_with_transition(&mut sprite, texture, 0.1)
+                .unwrap();
+            // the outgoing slot stays taken until the fade is over
+            assert_eq!(master.slot_stats().used, 2);
+            if each % 3 == 0 {
+                let sprites = unsafe { (*table).read_column::<Sprite>().unwrap() };
+                master.step_animations(sprites, &uniform).unwrap();
+                assert_eq!(master.slot_stats().used, 1);
+            }
+        }
+        assert_eq!(master.slot_stats().high_water, 2);
+    }
+
+    #[test]
+    fn crossfade_only_reports_events_of_the_incoming_animation() {
+        let mut table = Box::new(ecs::ECS::new(|_| {}).table);
+        let table = &mut *table as *mut ecs::Table;
+        let mut master = sprite_master(unsafe { &mut *table });
+        for name in ["idle", "run"] {
+            let texture = master.texture_id(name).unwrap();
+            let tex_data = &mut master.textures[texture.0 as usize];
+            tex_data.events = (0..tex_data.frames)
+                .map(|frame| FrameEvent {
+                    frame,
+                    name: name.to_string(),
+                })
+                .collect();
+        }
+        let mut sprite = master.add_sprite("idle", (0.0, 0.0), 0.0).unwrap();
+        master
+            .change_state_with_transition(&mut sprite, "run", 1.0)
+            .unwrap();
+
+        let mut uniform: Uniform = bytemuck::Zeroable::zeroed();
+        uniform.delta_time = 0.1;
+        let mut fired = vec![];
+        for _ in 0..8 {
+            let sprites = unsafe { (*table).read_column::<Sprite>().unwrap() };
+            master.step_animations(sprites, &uniform).unwrap();
+            let events = unsafe { (*table).read_state::<AnimationEvents>().unwrap() };
+            fired.extend(events.iter().map(|x| x.name.clone()));
+            events.reset();
+        }
+        assert!(fired.len() > 1);
+        assert!(fired.iter().all(|x| x == "run"));
+    }
+    fn strip_sprite(frames: u32) -> Sprite {
+        let mut sprite = Sprite::new_empty();
+        sprite.frames = frames;
+        sprite.looping = 1;
+        sprite
+    }
+
+    /// steps a fresh animation a whole second at a time and records the frame after each step
+    fn play(sprite: &Sprite, frame_durations: &[f32], steps: usize) -> (Vec<u32>, Animation) {
+        let mut anim = Animation::new_empty();
+        let frames = (0..steps)
+            .map(|_| {
+                anim.step(sprite, frame_durations, 1.0);
+                anim.current_frame
+            })
+            .collect();
+        (frames, anim)
+    }
+
+    #[test]
+    fn looping_animation_wraps_after_flagging_the_cycle() {
+        let sprite = strip_sprite(3);
+        let mut anim = Animation::new_empty();
+        let mut flags = vec![];
+        for _ in 0..8 {
+            anim.step(&sprite, &[], 1.0);
+            flags.push((anim.current_frame, anim.cycle_just_finished, anim.cycles));
+        }
+        assert_eq!(
+            flags,
+            [
+                (0, 0, 0),
+                (1, 0, 0),
+                (1, 0, 0),
+                (2, 0, 0),
+                (2, 0, 0),
+                (2, 1, 0),
+                (2, 2, 1),
+                (0, 0, 1),
+            ]
+        );
+        assert_eq!(anim.loop_paused, 0);
+    }
+
+    #[test]
+    fn non_looping_animation_holds_its_last_frame() {
+        let mut sprite = strip_sprite(3);
+        sprite.looping = 0;
+        let (frames, anim) = play(&sprite, &[], 10);
+        assert_eq!(frames, [0, 1, 1, 2, 2, 2, 2, 2, 2, 2]);
+        assert_eq!(anim.loop_paused, 1);
+        assert_eq!(anim.cycles, 0);
+    }
+
+    #[test]
+    fn reversed_animation_plays_from_the_last_frame() {
+        let mut sprite = strip_sprite(3);
+        sprite.reversed = 1;
+        let (frames, anim) = play(&sprite, &[], 8);
+        assert_eq!(frames, [2, 1, 1, 0, 0, 0, 0, 2]);
+        assert_eq!(anim.cycles, 1);
+    }
+
+    #[test]
+    fn ping_pong_bounces_and_ends_the_cycle_on_the_first_frame() {
+        let mut sprite = strip_sprite(3);
+        sprite.ping_pong = 1;
+        let (frames, anim) = play(&sprite, &[], 12);
+        assert_eq!(frames, [0, 1, 1, 2, 2, 1, 1, 0, 0, 0, 0, 0]);
+        assert_eq!(anim.cycles, 1);
+        assert_eq!(anim.backwards, 0);
+
+        sprite.reversed = 1;
+        let (frames, _) = play(&sprite, &[], 9);
+        assert_eq!(frames, [2, 1, 1, 0, 0, 1, 1, 2, 2]);
+
+        // ping pong without looping stops once it is back
+        sprite.reversed = 0;
+        sprite.looping = 0;
+        let (frames, anim) = play(&sprite, &[], 12);
+        assert_eq!(frames, [0, 1, 1, 2, 2, 1, 1, 0, 0, 0, 0, 0]);
+        assert_eq!(anim.loop_paused, 1);
+        assert_eq!(anim.cycles, 0);
+    }
+
+    #[test]
+    fn frame_durations_hold_each_frame_for_its_own_time() {
+        let mut sprite = strip_sprite(3);
+        // the first entry belongs to another texture
+        sprite.durations_offset = 1;
+        let (frames, _) = play(&sprite, &[9.0, 1.0, 3.0, 2.0], 10);
+        assert_eq!(frames, [0, 1, 1, 1, 1, 2, 2, 2, 2, 2]);
+    }
+
+    #[test]
+    fn speed_scales_the_frame_time() {
+        let mut sprite = strip_sprite(3);
+        sprite.duration = 2.0;
+        sprite.speed = 2.0;
+        let (frames, _) = play(&sprite, &[], 4);
+        assert_eq!(frames, [0, 1, 1, 2]);
+
+        sprite.speed = 0.0;
+        let (frames, _) = play(&sprite, &[], 4);
+        assert_eq!(frames, [0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn seek_and_set_progress_place_the_animation() {
+        let mut table = Box::new(ecs::ECS::new(|_| {}).table);
+        let mut master = sprite_master(&mut table);
+        let mut sprite = master.add_sprite("idle", (0.0, 0.0), 0.0).unwrap();
+        let index = sprite.anim_buffer_index as usize;
+
+        master.seek(&sprite, 2).unwrap();
+        assert_eq!(master.anim_data[index].current_frame, 2);
+        assert!(matches!(
+            master.seek(&sprite, 4),
+            Err(RendererError::FrameOutOfRange {
+                frame: 4,
+                frames: 4
+            })
+        ));
+        // stepping carries on from the frame that was jumped to
+        master.anim_data[index].step(&sprite, &[], 0.1);
+        master.anim_data[index].step(&sprite, &[], 0.1);
+        assert_eq!(master.anim_data[index].current_frame, 3);
+        assert_eq!(master.anim_data[index].loop_paused, 1);
+
+        master.set_progress(&sprite, 0.5).unwrap();
+        assert_eq!(master.anim_data[index].current_frame, 2);
+        master.set_progress(&sprite, 1.0).unwrap();
+        assert_eq!(master.anim_data[index].current_frame, 3);
+
+        // seek names the frame itself, progress counts in play order
+        sprite.reversed = 1;
+        master.seek(&sprite, 1).unwrap();
+        assert_eq!(master.anim_data[index].current_frame, 1);
+        master.set_progress(&sprite, 0.0).unwrap();
+        assert_eq!(master.anim_data[index].current_frame, 3);
+
+        // the way back is the second half of a ping pong cycle
+        sprite.reversed = 0;
+        sprite.ping_pong = 1;
+        master.set_progress(&sprite, 0.75).unwrap();
+        let anim = &master.anim_data[index];
+        assert_eq!((anim.current_frame, anim.backwards), (2, 1));
+        assert_eq!(anim.loop_paused, 0);
+    }
+
+    #[test]
+    fn restart_starts_the_cycle_count_over() {
+        let mut table = Box::new(ecs::ECS::new(|_| {}).table);
+        let mut master = sprite_master(&mut table);
+        let sprite = master.add_sprite("idle", (0.0, 0.0), 0.0).unwrap();
+        let index = sprite.anim_buffer_index as usize;
+        for _ in 0..20 {
+            master.anim_data[index].step(&sprite, &[], 0.1);
+        }
+        assert!(master.anim_data[index].cycles > 0);
+        master.restart(&sprite).unwrap();
+        let anim = &master.anim_data[index];
+        assert_eq!((anim.current_frame, anim.cycles, anim.counter), (0, 0, 0.0));
+    }
+    #[test]
+    fn queries_find_wide_rects_that_start_far_to_the_left() {
+        let mut table = Box::new(ecs::ECS::new(|_| {}).table);
+        table.register_column::<CollisionRect>();
+        let mut manager = CollisionManager::new(&mut *table);
+        let filter = CollisionFilter::ALL;
+        let mut add = |x: f32, width: f32| {
+            manager
+                .add_collision_rect((x, 1.0), (width, 2.0), 0.5, 1.0, filter)
+                .unwrap()
+                .sparse_index
+        };
+        let wide = add(-100.0, 200.0);
+        let near = add(10.0, 2.0);
+        let far = add(40.0, 2.0);
+        let left = add(-50.0, 2.0);
+        manager.update().unwrap();
+
+        let mut found = manager.query_point(11.0, 0.0, QueryFilter::default());
+        found.sort_unstable();
+        assert_eq!(found, [wide, near]);
+        let mut found = manager.query_aabb((30.0, -1.0), (45.0, 1.0), QueryFilter::default());
+        found.sort_unstable();
+        assert_eq!(found, [wide, far]);
+        let mut found = manager.query_point(-49.0, 0.0, QueryFilter::default());
+        found.sort_unstable();
+        assert_eq!(found, [wide, left]);
+        assert!(manager
+            .query_point(150.0, 0.0, QueryFilter::default())
+            .is_empty());
+        assert!(manager
+            .query_point(-101.0, 0.0, QueryFilter::default())
+            .is_empty());
+    }
+}
@@ -9,6 +9,10 @@ struct Uniform {
     delta_time: f32,
     global_offset_x: f32,
     global_offset_y: f32,
+    time_scale: f32,
+    paused: u32,
+    raw_delta_time: f32,
+    alpha: f32,
 }
 
 struct Sprite {
@@ -37,6 +41,25 @@ struct Sprite {
 
     flipped_x: u32,
     flipped_y: u32,
+
+    page: u32,
+    durations_offset: u32,
+    columns: u32,
+
+    fade_tex_x: f32,
+    fade_tex_y: f32,
+    fade_tex_width: f32,
+    fade_tex_height: f32,
+    fade_page: u32,
+    fade_columns: u32,
+
+    ping_pong: u32,
+    speed: f32,
+
+    prev_pos_x: f32,
+    prev_pos_y: f32,
+
+    anim_generation: u32,
 }
 
 struct Animation {
@@ -48,6 +71,12 @@ struct Animation {
     cycle_just_finished: u32,
 
     depth: f32,
+
+    fade_from: u32,
+    fade_elapsed: f32,
+    fade_duration: f32,
+
+    backwards: u32,
 }
 
 struct VertexOutput {
@@ -57,18 +86,27 @@ struct VertexOutput {
     @location(2) tex_height: i32,
     @location(3) tex_x: i32,
     @location(4) tex_y: i32,
-    @location(5) frame_offset_x: u32,
+    @location(5) current_frame: u32,
     @location(6) transparency: f32,
 
     @location(7) flipped_x: u32,
     @location(8) flipped_y: u32,
     @location(9) buffer_index: u32,
+    @location(10) page: u32,
+    @location(11) columns: u32,
+    // x, y, width and height of the outgoing animation
+    @location(12) fade_tex: vec4<i32>,
+    // page, columns and current frame of the outgoing animation
+    @location(13) fade_frame: vec3<u32>,
+    // weight of the incoming animation, 1 when not transitioning
+    @location(14) fade: f32,
 }
 
 @group(0) @binding(0) var<uniform> uniform_data: Uniform;
 @group(0) @binding(1) var<storage, read_write> storage_array: array<Sprite>;
-@group(0) @binding(2) var<storage, read_write> anim_storage_array: array<Animation>;
-@group(0) @binding(3) var texture: texture_2d<f32>;
+// stepped on the cpu, see `Animation::step`
+@group(0) @binding(2) var<storage, read> anim_storage_array: array<Animation>;
+@group(0) @binding(3) var texture: texture_2d_array<f32>;
 @group(0) @binding(4) var<storage, read_write> collision_array: array<u32>;
 
 @vertex
@@ -100,8 +138,6 @@ fn vs_main(@builtin(vertex_index) vertex_index: u32) -> VertexOutput {
         depth = 0.0;
     }
 
-    anim_storage_array[current_sprite.buffer_index].depth = depth;
-
     var out: VertexOutput;
     switch vertex_in_sprite_index {
         case 0u: {
@@ -139,108 +175,62 @@ fn vs_main(@builtin(vertex_index) vertex_index: u32) -> VertexOutput {
         }
     }
 
-    // conditionals may very well causing the gpu to branch, might wanna do something about this
-    let counter = &anim_storage_array[current_sprite.buffer_index].counter;
-    let current_frame = &anim_storage_array[current_sprite.buffer_index].current_frame;
-    let cycle_just_finished = &anim_storage_array[current_sprite.buffer_index].cycle_just_finished;
-    let cycles = &anim_storage_array[current_sprite.buffer_index].cycles;
-    let loop_paused = &anim_storage_array[current_sprite.buffer_index].loop_paused;
-    let started = &anim_storage_array[current_sprite.buffer_index].started;
-
-    var frame_offset_x = 0u;
-    if current_sprite.reversed != 0u && *started == 0u {
-        *current_frame = (current_sprite.frames - 1u);
-    }
-    if current_sprite.frames > 1u && current_sprite.paused == 0u && (current_sprite.looping != 0u || *loop_paused == 0u) {
-        *started = 1u;
-        let duration = max(current_sprite.duration, 0.0);
-        if *counter >= duration {
-            if current_sprite.reversed == 0u {
-                if *current_frame + 1u < current_sprite.frames {
-                    if *current_frame + 1u == current_sprite.frames - 1u {
-                        *loop_paused = 1u;
-                    }
-                    *current_frame += 1u;
-                    *counter = 0.0;
-                } else {
-                    if *cycle_just_finished == 0u {
-                        *cycle_just_finished += 1u;
-                    } else if *cycle_just_finished == 1u {
-                        *cycle_just_finished += 1u;
-                        *cycles += 1u;
-                    } else {
-                        *current_frame = 0u;
-                        *loop_paused = 0u;
-                        *cycle_just_finished = 0u;
-                        *counter = 0.0;
-                    }
-                }
-            } else {
-                if *current_frame > 0u {
-                    if *current_frame == 1u {
-                        *loop_paused = 1u;
-                    }
-                    *current_frame -= 1u;
-                    *counter = 0.0;
-                } else {
-                    if *cycle_just_finished == 0u {
-                        *cycle_just_finished += 1u;
-                    } else if *cycle_just_finished == 1u {
-                        *cycle_just_finished += 1u;
-                        *cycles += 1u;
-                    } else {
-                        *current_frame = current_sprite.frames - 1u;
-                        *loop_paused = 0u;
-                        *cycle_just_finished = 0u;
-                        *counter = 0.0;
-                    }
-                }
-            }
-        } else {
-            *counter += uniform_data.delta_time;
-        }
-    }
-    frame_offset_x = *current_frame * u32(current_sprite.tex_width);
-
     out.tex_width = i32(current_sprite.tex_width);
     out.tex_height = i32(current_sprite.tex_height);
     out.tex_x = i32(current_sprite.tex_x);
     out.tex_y = i32(current_sprite.tex_y);
-    out.frame_offset_x = frame_offset_x;
+    out.current_frame = anim_storage_array[current_sprite.buffer_index].current_frame;
     out.transparency = clamp(current_sprite.transparency, 0.0, 1.0);
     out.flipped_x = current_sprite.flipped_x;
     out.flipped_y = current_sprite.flipped_y;
     out.buffer_index = current_sprite.buffer_index;
+    out.page = current_sprite.page;
+    out.columns = current_sprite.columns;
+
+    let anim = anim_storage_array[current_sprite.buffer_index];
+    out.fade = 1.0;
+    if anim.fade_from != 0xffffffffu && anim.fade_duration > 0.0 {
+        out.fade = clamp(anim.fade_elapsed / anim.fade_duration, 0.0, 1.0);
+        out.fade_tex = vec4<i32>(i32(current_sprite.fade_tex_x), i32(current_sprite.fade_tex_y), i32(current_sprite.fade_tex_width), i32(current_sprite.fade_tex_height));
+        out.fade_frame = vec3<u32>(current_sprite.fade_page, current_sprite.fade_columns, anim_storage_array[anim.fade_from].current_frame);
+    }
 
     return out;
 }
 
-@fragment
-fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
-    var result: vec4<f32>;
-    // result = textureLoad(texture, vec2<i32>(i32(in.tex_coords.x) + i32(in.frame_offset_x), i32(in.tex_coords.y)), 0);
-
-    var repeated_unit_x = i32(in.tex_coords.x) % in.tex_width;
-    var repeated_unit_y = i32(in.tex_coords.y) % in.tex_height;
-    var base_x = in.tex_x;
-    var base_y = in.tex_y;
-    var frame_x = i32(in.frame_offset_x);
-    var frame_y = 0;
+// frames wrap onto the next row after `columns` frames
+fn sample_frame(coords: vec2<f32>, region: vec4<i32>, page: u32, columns: u32, frame: u32, flipped_x: u32, flipped_y: u32) -> vec4<f32> {
+    var repeated_unit_x = i32(coords.x) % region.z;
+    var repeated_unit_y = i32(coords.y) % region.w;
+    let wrapped_columns = max(columns, 1u);
+    let frame_x = i32(frame % wrapped_columns) * region.z;
+    let frame_y = i32(frame / wrapped_columns) * region.w;
 
     // should probably completely avoid conditionals in fragment shaders
     // diffirent branching on different invocations may cause problems
-    if in.flipped_x != 0u {
-        repeated_unit_x = in.tex_width - 1 - repeated_unit_x;
+    if flipped_x != 0u {
+        repeated_unit_x = region.z - 1 - repeated_unit_x;
     }
 
-    if in.flipped_y != 0u {
-        repeated_unit_y = in.tex_height - 1 - repeated_unit_y;
+    if flipped_y != 0u {
+        repeated_unit_y = region.w - 1 - repeated_unit_y;
     }
 
-    result = textureLoad(texture, vec2<i32>(base_x + frame_x + repeated_unit_x, base_y + frame_y + repeated_unit_y), 0);
+    return textureLoad(texture, vec2<i32>(region.x + frame_x + repeated_unit_x, region.y + frame_y + repeated_unit_y), i32(page), 0);
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    var result = sample_frame(in.tex_coords, vec4<i32>(in.tex_x, in.tex_y, in.tex_width, in.tex_height), in.page, in.columns, in.current_frame, in.flipped_x, in.flipped_y);
+
+    // crossfading from the previous state
+    if in.fade < 1.0 {
+        let outgoing = sample_frame(in.tex_coords, in.fade_tex, in.fade_frame.x, in.fade_frame.y, in.fade_frame.z, in.flipped_x, in.flipped_y);
+        result = mix(outgoing, result, in.fade);
+    }
 
     // set transparency
     result.w *= in.transparency;
     // result.x = abs(sin(uniform_data.utime));
     return result;
-}
\ No newline at end of file
+}